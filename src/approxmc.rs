@@ -0,0 +1,155 @@
+use num_bigint::BigUint;
+use rand::rngs::StdRng;
+use rand::Rng;
+
+use crate::clause::Clause;
+use crate::enumerate;
+use crate::solver_state::SolverState;
+use crate::xor::Xor;
+
+// Number of models a hash cell must stay below for its count to be scaled up, which keeps the
+// estimate within a factor of 1 + epsilon with high probability
+fn threshold(epsilon: f64) -> usize {
+    (1.0 + 9.84 * (1.0 + epsilon / (1.0 + epsilon)) * (1.0 + 1.0 / epsilon).powi(2)).ceil() as usize
+}
+
+// Number of estimates whose median fails with probability at most delta
+fn iterations(delta: f64) -> usize {
+    (17.0 * (3.0 / delta).log2()).ceil() as usize
+}
+
+// XOR over each sampling variable with probability one half and a random parity
+fn random_xor(sampling: &[usize], rng: &mut StdRng) -> Xor {
+    let vars = sampling.iter().copied().filter(|_| rng.gen::<bool>()).collect();
+    Xor::new(vars, rng.gen())
+}
+
+// Models of the formula projected onto the sampling set in the cell cut out by `hash`, counted
+// up to the threshold on a fresh copy of the formula so blocking clauses do not pile up
+fn cell_count(state: &mut SolverState, sampling: &[usize], hash: &[Xor], threshold: usize) -> usize {
+    let mut cell = SolverState::new();
+    cell.set_vars(state.vars());
+    for clause in &state.clauselist()[..state.original_clause_count] {
+        cell.add_clause(Clause::from_vec(clause.vars.clone()));
+    }
+    cell.original_clause_count = state.original_clause_count;
+    let mut config = state.config().clone();
+    config.report_interval = 0;
+    cell.set_config(config);
+    cell.add_independent_support(sampling);
+    for xor in state.xors().iter().chain(hash) {
+        cell.add_xor(xor.clone());
+    }
+    for pb in state.pbs() {
+        cell.add_pb(pb.clone());
+    }
+    let count = enumerate::models(&mut cell).take(threshold).count();
+
+    let (stats, cell_stats) = (state.stats_mut(), cell.stats());
+    stats.decisions += cell_stats.decisions;
+    stats.propagations += cell_stats.propagations;
+    stats.conflicts += cell_stats.conflicts;
+    stats.xor_explanations += cell_stats.xor_explanations;
+    stats.cells += 1;
+    count
+}
+
+/// Approximate number of models of the original clauses of `state`, projected onto the
+/// independent support if the formula has one, in the manner of ApproxMC.
+///
+/// Random XOR constraints over the sampling set split the models into cells of roughly equal
+/// size. Each round finds the fewest XORs from a random hash leaving a cell with fewer models
+/// than a threshold and scales its count by the number of cells; the median of the rounds is
+/// within a factor of `1 + epsilon` of the true count with probability at least `1 - delta`.
+/// Formulas with fewer models than the threshold are counted exactly. The hashes are drawn
+/// from the solver's random number generator, so the configured seed reproduces them.
+pub fn count(state: &mut SolverState) -> Result<BigUint, String> {
+    let (epsilon, delta) = (state.config().epsilon, state.config().delta);
+    if epsilon.is_nan() || epsilon <= 0.0 {
+        return Err(format!("epsilon must be positive, got {epsilon}"));
+    }
+    if delta.is_nan() || delta <= 0.0 || delta >= 1.0 {
+        return Err(format!("delta must be between 0 and 1, got {delta}"));
+    }
+    let sampling: Vec<usize> = match state.independent_support() {
+        Some(support) => support.iter().copied().filter(|var| *var <= state.vars()).collect(),
+        None => (1..=state.vars()).collect(),
+    };
+    let threshold = threshold(epsilon);
+
+    let models = cell_count(state, &sampling, &[], threshold);
+    if models < threshold {
+        return Ok(BigUint::from(models));
+    }
+
+    let mut estimates = Vec::new();
+    for _ in 0..iterations(delta) {
+        let hash: Vec<Xor> = sampling.iter().map(|_| random_xor(&sampling, state.rng())).collect();
+
+        // Cells only shrink as XORs of the hash are added, so gallop to a prefix leaving fewer
+        // models than the threshold and narrow it down by bisection
+        let mut low = 0;
+        let mut high = 1;
+        let mut high_count = cell_count(state, &sampling, &hash[..high], threshold);
+        while high_count >= threshold && high < sampling.len() {
+            low = high;
+            high = (2 * high).min(sampling.len());
+            high_count = cell_count(state, &sampling, &hash[..high], threshold);
+        }
+        while high_count < threshold && high - low > 1 {
+            let middle = (low + high) / 2;
+            let middle_count = cell_count(state, &sampling, &hash[..middle], threshold);
+            if middle_count < threshold {
+                high = middle;
+                high_count = middle_count;
+            } else {
+                low = middle;
+            }
+        }
+        estimates.push(BigUint::from(high_count) << high);
+    }
+
+    estimates.sort();
+    Ok(estimates.swap_remove(estimates.len() / 2))
+}
+
+#[cfg(test)]
+mod tests {
+    use num_bigint::BigUint;
+
+    use super::count;
+    use crate::parser::parse_reader;
+
+    #[test]
+    fn estimates_are_within_tolerance() {
+        // One clause over 9 variables has 511 models
+        let input = "p cnf 9 1\n1 2 3 4 5 6 7 8 9 0\n";
+        let approximate = || {
+            let mut state = parse_reader(input.as_bytes()).unwrap();
+            let mut config = state.config().clone();
+            config.delta = 0.5;
+            state.set_config(config);
+            (count(&mut state).unwrap(), state.stats().cells)
+        };
+        let (estimate, cells) = approximate();
+        assert!(estimate >= BigUint::from(511u32 * 10 / 18), "{estimate}");
+        assert!(estimate <= BigUint::from(511u32 * 18 / 10), "{estimate}");
+        assert!(cells > 1);
+
+        // The same seed draws the same hashes
+        assert_eq!(approximate().0, estimate);
+    }
+
+    #[test]
+    fn small_counts_are_exact() {
+        let input = "c ind 1 2 3 0\np cnf 5 3\n1 2 0\n-1 -2 0\n3 4 5 0\n";
+        let mut state = parse_reader(input.as_bytes()).unwrap();
+        assert_eq!(count(&mut state).unwrap(), BigUint::from(4u8));
+
+        let mut state = parse_reader(input.as_bytes()).unwrap();
+        let mut config = state.config().clone();
+        config.delta = 1.5;
+        state.set_config(config);
+        assert!(count(&mut state).is_err());
+    }
+}
@@ -1,7 +1,9 @@
+use std::fmt;
 use std::ops::Index;
 
 use crate::solver_state::SolverMove;
 
+#[derive(Clone)]
 pub struct Assignment {
     assignments: Vec<Option<bool>>,
 }
@@ -15,31 +17,72 @@ impl Assignment {
                     variable,
                     clause: _,
                 } => *variable,
+                SolverMove::PropagateConstraint {
+                    variable,
+                    reason: _,
+                } => *variable,
                 SolverMove::Decide(variable) => *variable,
                 SolverMove::DecideFromConflict(variable, _) => *variable,
                 SolverMove::Sat() => {
                     panic!("Attempted to generate assignment from completed movelist")
                 }
-                SolverMove::Conflict(_) => {
+                SolverMove::Conflict(_) | SolverMove::ConstraintConflict(_) => {
                     panic!("Attempted to generate assignment from movelist with conflict")
                 }
             };
-            let index: usize = variable.abs() as usize;
+            let index: usize = variable.unsigned_abs() as usize;
             initial_assignments[index - 1] = Some(variable > 0); //Variable '1' maps to assignments[0]
         }
 
-        return Assignment {
+        Assignment {
             assignments: initial_assignments,
+        }
+    }
+
+    /// Assignment of `vars` variables making the given literals true
+    pub fn from_literals(literals: &[i32], vars: usize) -> Assignment {
+        let mut assignment = Assignment {
+            assignments: vec![None; vars],
         };
+        for literal in literals {
+            assignment.assign(*literal);
+        }
+        assignment
     }
 
     pub fn len(&self) -> usize {
-        return self.assignments.len();
+        self.assignments.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.assignments.is_empty()
+    }
+
+    /// Truth value of `literal`, if its variable is assigned
+    pub fn value(&self, literal: i32) -> Option<bool> {
+        self[literal.unsigned_abs() as usize].map(|val| val == (literal > 0))
+    }
+
+    /// Makes `literal` true, overriding any previous value of its variable
+    pub fn assign(&mut self, literal: i32) {
+        self.assignments[literal.unsigned_abs() as usize - 1] = Some(literal > 0);
+    }
+
+    /// Assigned variables as DIMACS literals, in variable order
+    pub fn literals(&self) -> Vec<i32> {
+        let mut literals = Vec::new();
+        for i in 1..=self.assignments.len() {
+            if let Some(val) = self[i] {
+                let var = i as i32;
+                literals.push(if val { var } else { -var });
+            }
+        }
+        literals
     }
 }
 
-impl ToString for Assignment {
-    fn to_string(&self) -> String {
+impl fmt::Display for Assignment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut buf = "".to_owned();
         for i in 1..=self.assignments.len() {
             match self[i] {
@@ -54,7 +97,7 @@ impl ToString for Assignment {
             }
         }
 
-        return buf;
+        write!(f, "{}", buf)
     }
 }
 
@@ -62,6 +105,6 @@ impl Index<usize> for Assignment {
     type Output = Option<bool>;
 
     fn index(&self, index: usize) -> &Self::Output {
-        return &self.assignments[index - 1];
+        &self.assignments[index - 1]
     }
 }
@@ -21,7 +21,7 @@ impl Clause {
     pub fn check_assignment(&self, assignment: &Assignment) -> Option<AssignmentResult> {
         let mut last_available: Option<i32> = None;
         for var in &self.vars {
-            let index: usize = var.abs() as usize;
+            let index: usize = var.unsigned_abs() as usize;
             let clause_val = *var > 0;
             let var_assignment = assignment[index];
             match var_assignment {
@@ -64,7 +64,7 @@ mod tests {
         let clause = super::Clause::from_vec(test_vec);
         let test_movelist = vec![SolverMove::Decide(-1), SolverMove::Decide(-3), SolverMove::Decide(-5)];
         let assignment = Assignment::from_movelist(&test_movelist, 5);
-        println!("{}", assignment.to_string());
+        println!("{}", assignment);
         let result = clause.check_assignment(&assignment);
         assert_eq!(result.unwrap(), AssignmentResult::Conflict());
 
@@ -76,7 +76,7 @@ mod tests {
         let clause = super::Clause::from_vec(test_vec);
         let test_movelist = vec![SolverMove::Decide(-1), SolverMove::Decide(-3)];
         let assignment = Assignment::from_movelist(&test_movelist, 5);
-        println!("{}", assignment.to_string());
+        println!("{}", assignment);
         let result = clause.check_assignment(&assignment);
         assert_eq!(result.unwrap(), AssignmentResult::Propagate(5));
 
@@ -88,7 +88,7 @@ mod tests {
         let clause = super::Clause::from_vec(test_vec);
         let test_movelist = vec![SolverMove::Decide(-1), SolverMove::Decide(5)];
         let assignment = Assignment::from_movelist(&test_movelist, 5);
-        println!("{}", assignment.to_string());
+        println!("{}", assignment);
         let result = clause.check_assignment(&assignment);
         assert_eq!(result.unwrap(), AssignmentResult::Sat());
 
@@ -100,7 +100,7 @@ mod tests {
         let clause = super::Clause::from_vec(test_vec);
         let test_movelist = vec![SolverMove::Decide(-1)];
         let assignment = Assignment::from_movelist(&test_movelist, 5);
-        println!("{}", assignment.to_string());
+        println!("{}", assignment);
         let result = clause.check_assignment(&assignment);
         assert_eq!(result, None);
 
@@ -112,7 +112,7 @@ mod tests {
         let clause = super::Clause::from_vec(test_vec);
         let test_movelist = vec![SolverMove::Decide(1)];
         let assignment = Assignment::from_movelist(&test_movelist, 5);
-        println!("{}", assignment.to_string());
+        println!("{}", assignment);
         let result = clause.check_assignment(&assignment);
         assert_eq!(result, None);
 
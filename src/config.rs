@@ -0,0 +1,344 @@
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::solver_state::DEFAULT_SEED;
+
+/// Conflict resolution strategy
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Algorithm {
+    Cdcl,
+    Dpll,
+    // Local search alone, reporting the best assignment when no model is found
+    Sls,
+}
+
+/// How local search picks the variable to flip in an unsatisfied clause
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SlsMethod {
+    Walksat,
+    Probsat,
+}
+
+/// How MaxSAT looks for an optimum
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MaxSatMethod {
+    // Core-guided, raising a lower bound by the unsatisfiable cores found under assumptions
+    Oll,
+    // SAT-UNSAT search, tightening an upper bound with each model found
+    Linear,
+}
+
+/// Heuristic used to pick the next decision literal
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Decision {
+    FirstUnsat,
+    Bohm,
+    Activity,
+    Random,
+    Vmtf,
+    Lrb,
+    Chb,
+    Jw,
+    JwTwoSided,
+    Moms,
+    Dlis,
+    Dlcs,
+}
+
+/// Every tunable choice made by the solver.
+///
+/// Built from a preset, a TOML/JSON file, `--key=value` options or directly through the fields.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct SolverConfig {
+    pub algorithm: Algorithm,
+    pub decision: Decision,
+    // Factor all activities are multiplied by after each conflict, under CDCL and under DPLL
+    pub decay: f64,
+    pub dpll_decay: f64,
+    // Chance that a conflict is followed by a round of clause forgetting
+    pub forget_probability: f64,
+    // Scales the chance of each unused learned clause being forgotten in a round
+    pub forget_fraction: f64,
+    // Backjumps over more levels than this only undo the conflict level (chronological
+    // backtracking), which the default never does
+    pub chrono_threshold: usize,
+    // Weights of the larger and smaller polarity count in the Bohm score
+    pub bohm_alpha: usize,
+    pub bohm_beta: usize,
+    // Exponent weighting occurrence counts over their product in the MOMS score
+    pub moms_k: i32,
+    // Step size of the LRB and CHB score averages, shrinking by the decay per conflict to the minimum
+    pub lrb_step: f64,
+    pub lrb_min_step: f64,
+    pub lrb_step_decay: f64,
+    // Failed literal probing and equivalent literal substitution before search
+    pub probe: bool,
+    pub substitute: bool,
+    // Bounded variable elimination before search, skipping variables with more occurrences than
+    // the occurrence limit or a resolvent longer than the clause limit
+    pub eliminate: bool,
+    pub elim_occurrence_limit: usize,
+    pub elim_clause_limit: usize,
+    // Subsumption and strengthening before search, and on learned clauses every interval
+    // conflicts during search (0 disables the latter)
+    pub subsume: bool,
+    pub subsume_interval: usize,
+    // Conflicts between vivification rounds (0 disables them), and the share of the search
+    // propagations vivification may spend
+    pub vivify_interval: usize,
+    pub vivify_effort: f64,
+    // Blocked clause elimination before search, extended to covered clauses if enabled
+    pub block: bool,
+    pub covered: bool,
+    // Propagate XOR constraints by Gauss-Jordan elimination over all of them rather than one
+    // constraint at a time
+    pub gauss: bool,
+    // WalkSAT flips a variable breaking the fewest clauses, or a random one at the noise rate
+    // when every flip breaks some clause. ProbSAT flips with probability cb^-breaks.
+    pub sls: SlsMethod,
+    pub walksat_noise: f64,
+    pub probsat_cb: f64,
+    // Flips of a local search run for `--algorithm=sls`
+    pub sls_flips: usize,
+    // Conflicts between local search runs setting the decision phases (0 disables them), and
+    // the flips each run may make
+    pub rephase_interval: usize,
+    pub rephase_flips: usize,
+    // Approximate counts are within a factor of 1 + epsilon of the model count with probability
+    // at least 1 - delta
+    pub epsilon: f64,
+    pub delta: f64,
+    pub maxsat: MaxSatMethod,
+    pub seed: u64,
+    // Conflicts between progress reports, 0 disables them
+    pub report_interval: usize,
+}
+
+impl SolverConfig {
+    pub fn new() -> SolverConfig {
+        SolverConfig {
+            algorithm: Algorithm::Cdcl,
+            decision: Decision::Activity,
+            decay: 0.50,
+            dpll_decay: 0.95,
+            forget_probability: 0.05,
+            forget_fraction: 0.0001,
+            chrono_threshold: usize::MAX,
+            bohm_alpha: 1,
+            bohm_beta: 2,
+            moms_k: 10,
+            lrb_step: 0.4,
+            lrb_min_step: 0.06,
+            lrb_step_decay: 0.000001,
+            probe: false,
+            substitute: false,
+            eliminate: false,
+            elim_occurrence_limit: 100,
+            elim_clause_limit: 20,
+            subsume: false,
+            subsume_interval: 0,
+            vivify_interval: 0,
+            vivify_effort: 0.1,
+            block: false,
+            covered: false,
+            gauss: true,
+            sls: SlsMethod::Probsat,
+            walksat_noise: 0.5,
+            probsat_cb: 2.5,
+            sls_flips: 1_000_000,
+            rephase_interval: 0,
+            rephase_flips: 10_000,
+            epsilon: 0.8,
+            delta: 0.2,
+            maxsat: MaxSatMethod::Oll,
+            seed: DEFAULT_SEED,
+            report_interval: 1000,
+        }
+    }
+
+    /// Named starting points: `default`, `sat` for instances expected to be satisfiable
+    /// and `unsat` for instances expected to be unsatisfiable
+    pub fn preset(name: &str) -> Option<SolverConfig> {
+        let mut config = SolverConfig::new();
+        match name {
+            "default" => (),
+            "sat" => {
+                // Keep activity from older conflicts and forget more often to stay agile
+                config.decay = 0.80;
+                config.forget_probability = 0.10;
+            }
+            "unsat" => {
+                // Focus on recent conflicts and keep every learned clause
+                config.decay = 0.40;
+                config.forget_probability = 0.0;
+            }
+            _ => return None,
+        }
+        Some(config)
+    }
+
+    /// Turns off the preprocessing passes that keep satisfiability but change the set of models,
+    /// for runs that enumerate or count models
+    pub fn preserve_models(&mut self) {
+        self.substitute = false;
+        self.eliminate = false;
+        self.block = false;
+        self.covered = false;
+    }
+
+    /// Reads a configuration file, as JSON if the extension is `.json` and as TOML otherwise.
+    /// Options missing from the file keep their default values.
+    pub fn from_file(path: &str) -> Result<SolverConfig, Box<dyn Error>> {
+        let contents = fs::read_to_string(path)?;
+        match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+            Some("json") => Ok(serde_json::from_str(&contents)?),
+            _ => Ok(toml::from_str(&contents)?),
+        }
+    }
+
+    /// Sets one option from its command line spelling, e.g. `set("decay", "0.95")`
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        fn parse<T: std::str::FromStr>(key: &str, value: &str) -> Result<T, String> {
+            value
+                .parse::<T>()
+                .map_err(|_| format!("Invalid value '{value}' for {key}"))
+        }
+
+        match key {
+            "algorithm" => {
+                self.algorithm = match value {
+                    "cdcl" => Algorithm::Cdcl,
+                    "dpll" => Algorithm::Dpll,
+                    "sls" => Algorithm::Sls,
+                    _ => return Err(format!("Unknown algorithm '{value}'")),
+                }
+            }
+            "decision" => {
+                self.decision = match value {
+                    "first-unsat" => Decision::FirstUnsat,
+                    "bohm" => Decision::Bohm,
+                    "activity" => Decision::Activity,
+                    "random" => Decision::Random,
+                    "vmtf" => Decision::Vmtf,
+                    "lrb" => Decision::Lrb,
+                    "chb" => Decision::Chb,
+                    "jw" => Decision::Jw,
+                    "jw-two-sided" => Decision::JwTwoSided,
+                    "moms" => Decision::Moms,
+                    "dlis" => Decision::Dlis,
+                    "dlcs" => Decision::Dlcs,
+                    _ => return Err(format!("Unknown decision heuristic '{value}'")),
+                }
+            }
+            "decay" => self.decay = parse(key, value)?,
+            "dpll-decay" => self.dpll_decay = parse(key, value)?,
+            "forget-probability" => self.forget_probability = parse(key, value)?,
+            "forget-fraction" => self.forget_fraction = parse(key, value)?,
+            "chrono-threshold" => self.chrono_threshold = parse(key, value)?,
+            "bohm-alpha" => self.bohm_alpha = parse(key, value)?,
+            "bohm-beta" => self.bohm_beta = parse(key, value)?,
+            "moms-k" => self.moms_k = parse(key, value)?,
+            "lrb-step" => self.lrb_step = parse(key, value)?,
+            "lrb-min-step" => self.lrb_min_step = parse(key, value)?,
+            "lrb-step-decay" => self.lrb_step_decay = parse(key, value)?,
+            "probe" => self.probe = parse(key, value)?,
+            "substitute" => self.substitute = parse(key, value)?,
+            "eliminate" => self.eliminate = parse(key, value)?,
+            "elim-occurrence-limit" => self.elim_occurrence_limit = parse(key, value)?,
+            "elim-clause-limit" => self.elim_clause_limit = parse(key, value)?,
+            "subsume" => self.subsume = parse(key, value)?,
+            "subsume-interval" => self.subsume_interval = parse(key, value)?,
+            "vivify-interval" => self.vivify_interval = parse(key, value)?,
+            "vivify-effort" => self.vivify_effort = parse(key, value)?,
+            "block" => self.block = parse(key, value)?,
+            "covered" => self.covered = parse(key, value)?,
+            "gauss" => self.gauss = parse(key, value)?,
+            "sls" => {
+                self.sls = match value {
+                    "walksat" => SlsMethod::Walksat,
+                    "probsat" => SlsMethod::Probsat,
+                    _ => return Err(format!("Unknown local search method '{value}'")),
+                }
+            }
+            "walksat-noise" => self.walksat_noise = parse(key, value)?,
+            "probsat-cb" => self.probsat_cb = parse(key, value)?,
+            "sls-flips" => self.sls_flips = parse(key, value)?,
+            "rephase-interval" => self.rephase_interval = parse(key, value)?,
+            "rephase-flips" => self.rephase_flips = parse(key, value)?,
+            "epsilon" => self.epsilon = parse(key, value)?,
+            "delta" => self.delta = parse(key, value)?,
+            "maxsat" => {
+                self.maxsat = match value {
+                    "oll" => MaxSatMethod::Oll,
+                    "linear" => MaxSatMethod::Linear,
+                    _ => return Err(format!("Unknown MaxSAT method '{value}'")),
+                }
+            }
+            "seed" => self.seed = parse(key, value)?,
+            "report-interval" => self.report_interval = parse(key, value)?,
+            _ => return Err(format!("Unknown option '{key}'")),
+        }
+        Ok(())
+    }
+}
+
+impl Default for SolverConfig {
+    fn default() -> Self {
+        SolverConfig::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Algorithm, Decision, MaxSatMethod, SlsMethod, SolverConfig};
+
+    #[test]
+    fn set_from_cli_values() {
+        let mut config = SolverConfig::new();
+        config.set("decision", "bohm").unwrap();
+        config.set("decay", "0.95").unwrap();
+        config.set("algorithm", "dpll").unwrap();
+        assert_eq!(config.decision, Decision::Bohm);
+        assert_eq!(config.decay, 0.95);
+        assert_eq!(config.algorithm, Algorithm::Dpll);
+        config.set("sls", "walksat").unwrap();
+        assert_eq!(config.sls, SlsMethod::Walksat);
+        config.set("maxsat", "linear").unwrap();
+        assert_eq!(config.maxsat, MaxSatMethod::Linear);
+
+        assert!(config.set("decision", "coin-flip").is_err());
+        assert!(config.set("decay", "fast").is_err());
+        assert!(config.set("speed", "1").is_err());
+    }
+
+    #[test]
+    fn presets() {
+        assert_eq!(SolverConfig::preset("default"), Some(SolverConfig::new()));
+        assert_eq!(SolverConfig::preset("unsat").unwrap().forget_probability, 0.0);
+        assert!(SolverConfig::preset("unsat").unwrap().decay < SolverConfig::new().decay);
+        assert!(SolverConfig::preset("sat").is_some());
+        assert!(SolverConfig::preset("fastest").is_none());
+    }
+
+    #[test]
+    fn file_formats_fill_in_defaults() {
+        let from_toml: SolverConfig = toml::from_str("decision = \"first-unsat\"\ndecay = 0.9\n").unwrap();
+        assert_eq!(from_toml.decision, Decision::FirstUnsat);
+        assert_eq!(from_toml.decay, 0.9);
+        assert_eq!(from_toml.seed, SolverConfig::new().seed);
+
+        let from_json: SolverConfig = serde_json::from_str("{\"algorithm\": \"dpll\", \"seed\": 7}").unwrap();
+        assert_eq!(from_json.algorithm, Algorithm::Dpll);
+        assert_eq!(from_json.seed, 7);
+
+        assert!(toml::from_str::<SolverConfig>("speed = 1").is_err());
+    }
+}
@@ -0,0 +1,408 @@
+use std::collections::{BTreeSet, HashMap};
+
+use num_bigint::BigUint;
+
+use crate::assignment::Assignment;
+use crate::clause::Clause;
+use crate::config::SolverConfig;
+use crate::pb::Pb;
+use crate::solver::{propagation_from_state, solve};
+use crate::solver_state::{SolverMove, SolverState};
+use crate::xor::Xor;
+
+// Constraint of the formula left over under the trail, over its unassigned variables
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+enum Residual {
+    Clause(Vec<i32>),
+    Xor(Vec<usize>, bool),
+    Pb(Vec<(u64, i32)>, u64),
+}
+
+impl Residual {
+    fn vars(&self) -> Vec<usize> {
+        match self {
+            Residual::Clause(literals) => literals.iter().map(|literal| literal.unsigned_abs() as usize).collect(),
+            Residual::Xor(vars, _) => vars.clone(),
+            Residual::Pb(terms, _) => terms.iter().map(|(_, literal)| literal.unsigned_abs() as usize).collect(),
+        }
+    }
+}
+
+// Clauses, XOR and pseudo-Boolean constraints not yet satisfied under `assignment`, reduced to
+// their unassigned variables
+fn residuals(state: &SolverState, assignment: &Assignment) -> Vec<Residual> {
+    let mut residuals = Vec::new();
+    for clause in state.clauselist() {
+        if clause.vars.iter().any(|literal| assignment.value(*literal) == Some(true)) {
+            continue;
+        }
+        let mut literals: Vec<i32> =
+            clause.vars.iter().copied().filter(|literal| assignment.value(*literal).is_none()).collect();
+        literals.sort();
+        literals.dedup();
+        residuals.push(Residual::Clause(literals));
+    }
+    for xor in state.xors() {
+        let vars: Vec<usize> = xor.vars.iter().copied().filter(|var| assignment[*var].is_none()).collect();
+        let parity = xor.vars.iter().fold(xor.parity, |parity, var| parity ^ (assignment[*var] == Some(true)));
+        if !vars.is_empty() {
+            residuals.push(Residual::Xor(vars, parity));
+        }
+    }
+    for pb in state.pbs() {
+        let satisfied: u64 = pb
+            .terms
+            .iter()
+            .filter(|(_, literal)| assignment.value(*literal) == Some(true))
+            .map(|(coefficient, _)| coefficient)
+            .sum();
+        if satisfied >= pb.bound {
+            continue;
+        }
+        let bound = pb.bound - satisfied;
+        let mut terms: Vec<(u64, i32)> = pb
+            .terms
+            .iter()
+            .filter(|(_, literal)| assignment.value(*literal).is_none())
+            .map(|(coefficient, literal)| ((*coefficient).min(bound), *literal))
+            .collect();
+        terms.sort_by_key(|(coefficient, literal)| (std::cmp::Reverse(*coefficient), literal.unsigned_abs()));
+        residuals.push(Residual::Pb(terms, bound));
+    }
+    residuals
+}
+
+// Splits constraints into groups sharing no variables, each in a canonical order for the cache
+fn components(residuals: Vec<Residual>) -> Vec<Vec<Residual>> {
+    fn find(parent: &mut HashMap<usize, usize>, var: usize) -> usize {
+        let mut root = var;
+        while parent[&root] != root {
+            root = parent[&root];
+        }
+        parent.insert(var, root);
+        root
+    }
+
+    let mut parent: HashMap<usize, usize> = HashMap::new();
+    for residual in &residuals {
+        let vars = residual.vars();
+        for var in &vars {
+            parent.entry(*var).or_insert(*var);
+        }
+        for pair in vars.windows(2) {
+            let first = find(&mut parent, pair[0]);
+            let second = find(&mut parent, pair[1]);
+            parent.insert(first, second);
+        }
+    }
+
+    let mut groups: HashMap<usize, Vec<Residual>> = HashMap::new();
+    for residual in residuals {
+        let root = find(&mut parent, residual.vars()[0]);
+        groups.entry(root).or_default().push(residual);
+    }
+    let mut components: Vec<Vec<Residual>> = groups
+        .into_values()
+        .map(|mut component| {
+            component.sort();
+            component.dedup();
+            component
+        })
+        .collect();
+    components.sort();
+    components
+}
+
+// Propagates the trail of `state` as the search does, returning false on a conflict
+fn propagate(state: &mut SolverState) -> bool {
+    while let Some(next_move) = propagation_from_state(state) {
+        match next_move {
+            SolverMove::Conflict(_) | SolverMove::ConstraintConflict(_) => return false,
+            _ => state.add_move(next_move),
+        }
+    }
+    true
+}
+
+// Pending work of the counter, kept on an explicit stack instead of recursing
+enum Frame {
+    // Product of the counts of the components left at a node, times its free variables
+    Product { components: Vec<Vec<Residual>>, result: BigUint },
+    // Sum of the counts of a component under both values of `var`, tried in turn from `level`
+    Branch {
+        component: Vec<Residual>,
+        scope: Vec<usize>,
+        var: usize,
+        level: usize,
+        tried: usize,
+        result: BigUint,
+    },
+}
+
+/// Exact model counter: a DPLL search on the trail of the solver, propagating clauses, XOR and
+/// pseudo-Boolean constraints as the search does, that splits the constraints left into
+/// components without shared variables, multiplies their counts and caches the count of every
+/// component it has seen.
+///
+/// When counting projected models only projection variables are branched on, and a component
+/// without any is checked for satisfiability with the CDCL solver.
+struct Counter {
+    // Whether each variable is counted, index 0 unused
+    projected: Vec<bool>,
+    cache: HashMap<Vec<Residual>, BigUint>,
+    config: SolverConfig,
+    components: usize,
+    cache_hits: usize,
+}
+
+impl Counter {
+    // Models of the formula of `state` under its propagated trail
+    fn count(&mut self, state: &mut SolverState) -> BigUint {
+        if !propagate(state) {
+            return BigUint::from(0u8);
+        }
+        let scope: Vec<usize> = (1..=state.vars()).collect();
+        let mut stack = Vec::new();
+        // Count of the last finished frame, None when a frame was just pushed
+        let mut returned = match self.node(state, &scope) {
+            Ok(count) => return count,
+            Err(frame) => {
+                stack.push(frame);
+                None
+            }
+        };
+        while let Some(frame) = stack.pop() {
+            match frame {
+                Frame::Product {
+                    mut components,
+                    mut result,
+                } => {
+                    if let Some(count) = returned.take() {
+                        result *= count;
+                    }
+                    match components.pop() {
+                        Some(component) if result != BigUint::from(0u8) => {
+                            stack.push(Frame::Product { components, result });
+                            match self.component(state, component) {
+                                Ok(count) => returned = Some(count),
+                                Err(frame) => stack.push(frame),
+                            }
+                        }
+                        _ => returned = Some(result),
+                    }
+                }
+                Frame::Branch {
+                    component,
+                    scope,
+                    var,
+                    level,
+                    tried,
+                    mut result,
+                } => {
+                    if let Some(count) = returned.take() {
+                        result += count;
+                    }
+                    state.backtrack(level);
+                    if tried == 2 {
+                        self.cache.insert(component, result.clone());
+                        returned = Some(result);
+                        continue;
+                    }
+                    let literal = if tried == 0 { var as i32 } else { -(var as i32) };
+                    state.add_decision_lv();
+                    state.add_move(SolverMove::Decide(literal));
+                    let child = if propagate(state) {
+                        self.node(state, &scope)
+                    } else {
+                        Ok(BigUint::from(0u8))
+                    };
+                    stack.push(Frame::Branch {
+                        component,
+                        scope,
+                        var,
+                        level,
+                        tried: tried + 1,
+                        result,
+                    });
+                    match child {
+                        Ok(count) => returned = Some(count),
+                        Err(frame) => stack.push(frame),
+                    }
+                }
+            }
+        }
+        returned.unwrap()
+    }
+
+    // Count of the variables in `scope` under the trail if no constraint is left over them, or
+    // else the product of the components left to count
+    fn node(&mut self, state: &SolverState, scope: &[usize]) -> Result<BigUint, Frame> {
+        let assignment = Assignment::from_movelist(&state.get_movelist(), state.vars());
+        let mut in_scope = vec![false; state.vars() + 1];
+        for var in scope {
+            in_scope[*var] = true;
+        }
+        let residuals: Vec<Residual> = residuals(state, &assignment)
+            .into_iter()
+            .filter(|residual| residual.vars().iter().any(|var| in_scope[*var]))
+            .collect();
+        let occurring: BTreeSet<usize> = residuals.iter().flat_map(|residual| residual.vars()).collect();
+        let free = scope
+            .iter()
+            .filter(|var| self.projected[**var] && assignment[**var].is_none() && !occurring.contains(var))
+            .count();
+
+        let result = BigUint::from(1u8) << free;
+        let components = components(residuals);
+        if components.is_empty() {
+            return Ok(result);
+        }
+        Err(Frame::Product { components, result })
+    }
+
+    // Count of a component if it is cached or has no projection variable to branch on, or else
+    // the branch on its most frequent projection variable
+    fn component(&mut self, state: &SolverState, component: Vec<Residual>) -> Result<BigUint, Frame> {
+        self.components += 1;
+        if let Some(count) = self.cache.get(&component) {
+            self.cache_hits += 1;
+            return Ok(count.clone());
+        }
+
+        let mut occurrences: HashMap<usize, usize> = HashMap::new();
+        for var in component.iter().flat_map(|residual| residual.vars()) {
+            *occurrences.entry(var).or_default() += 1;
+        }
+        let branch = occurrences
+            .iter()
+            .filter(|(var, _)| self.projected[**var])
+            .max_by_key(|(var, count)| (**count, std::cmp::Reverse(**var)))
+            .map(|(var, _)| *var);
+
+        match branch {
+            Some(var) => {
+                let mut scope: Vec<usize> = occurrences.keys().copied().collect();
+                scope.sort();
+                Err(Frame::Branch {
+                    component,
+                    scope,
+                    var,
+                    level: state.trail_depth(),
+                    tried: 0,
+                    result: BigUint::from(0u8),
+                })
+            }
+            None => {
+                let count = BigUint::from(self.satisfiable(state.vars(), &component) as u8);
+                self.cache.insert(component, count.clone());
+                Ok(count)
+            }
+        }
+    }
+
+    fn satisfiable(&self, vars: usize, component: &[Residual]) -> bool {
+        let mut state = SolverState::new();
+        state.set_vars(vars);
+        for residual in component {
+            match residual {
+                Residual::Clause(literals) => {
+                    state.add_clause(Clause::from_vec(literals.clone()));
+                    state.original_clause_count += 1;
+                }
+                Residual::Xor(vars, parity) => state.add_xor(Xor::new(vars.clone(), *parity)),
+                Residual::Pb(terms, bound) => state.add_pb(Pb {
+                    terms: terms.clone(),
+                    bound: *bound,
+                }),
+            }
+        }
+        state.set_config(self.config.clone());
+        solve(&mut state)
+    }
+}
+
+/// Number of models of the clauses, XOR and pseudo-Boolean constraints of `state`, or of their
+/// projections onto the independent support if the formula has one. Preprocessing passes that
+/// change the models must be off, see `SolverConfig::preserve_models`.
+pub fn count(state: &mut SolverState) -> BigUint {
+    state.reset_trail();
+    let mut projected = vec![state.independent_support().is_none(); state.vars() + 1];
+    for var in state.independent_support().unwrap_or_default() {
+        if let Some(projected) = projected.get_mut(*var) {
+            *projected = true;
+        }
+    }
+    let mut config = state.config().clone();
+    config.report_interval = 0;
+    let mut counter = Counter {
+        projected,
+        cache: HashMap::new(),
+        config,
+        components: 0,
+        cache_hits: 0,
+    };
+
+    let result = counter.count(state);
+    state.reset_trail();
+
+    let stats = state.stats_mut();
+    stats.components += counter.components;
+    stats.cache_hits += counter.cache_hits;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use num_bigint::BigUint;
+
+    use super::count;
+    use crate::parser::parse_reader;
+    use crate::testutil::brute_force_models;
+
+    // Number of distinct projections of the models onto `projection`, by brute force
+    fn brute_force(input: &str, projection: &[i32]) -> usize {
+        let state = parse_reader(input.as_bytes()).unwrap();
+        let models = brute_force_models(&state);
+        models.iter().map(|model| projection.iter().map(|var| model.contains(var)).collect::<Vec<bool>>()).collect::<BTreeSet<_>>().len()
+    }
+
+    #[test]
+    fn counts_match_brute_force() {
+        // Two independent parts, one of them repeated so the cache is used, and a free variable
+        let input = "p cnf 9 6\n1 2 0\n-1 3 0\n-2 -3 0\n4 5 0\n-4 6 0\n-5 -6 7 8 0\n";
+        let mut state = parse_reader(input.as_bytes()).unwrap();
+        let expected = brute_force(input, &[1, 2, 3, 4, 5, 6, 7, 8, 9]);
+        assert_eq!(count(&mut state), BigUint::from(expected));
+        assert!(state.stats().components > 1);
+
+        let unsat = "p cnf 2 4\n1 2 0\n-1 -2 0\n1 -2 0\n-1 2 0\n";
+        let mut state = parse_reader(unsat.as_bytes()).unwrap();
+        assert_eq!(count(&mut state), BigUint::from(0u8));
+    }
+
+    #[test]
+    fn projected_counts_and_big_results() {
+        let input = "c ind 1 2 5 0\np cnf 5 4\n1 2 3 0\n-3 4 0\n-1 -2 0\n-4 -5 0\n";
+        let mut state = parse_reader(input.as_bytes()).unwrap();
+        assert_eq!(count(&mut state), BigUint::from(brute_force(input, &[1, 2, 5])));
+
+        // A formula with one clause over 100 variables has 2^100 - 1 models
+        let clause: Vec<String> = (1..=100).map(|var| var.to_string()).collect();
+        let input = format!("p cnf 100 1\n{} 0\n", clause.join(" "));
+        let mut state = parse_reader(input.as_bytes()).unwrap();
+        assert_eq!(count(&mut state), (BigUint::from(1u8) << 100) - 1u8);
+
+        // So has at least one of them true as a cardinality constraint, which is counted directly
+        let input = format!("p cnf+ 100 1\n{} >= 1\n", clause.join(" "));
+        let mut state = parse_reader(input.as_bytes()).unwrap();
+        assert_eq!(count(&mut state), (BigUint::from(1u8) << 100) - 1u8);
+
+        // Branching once per variable nests a thousand branches, kept on the work stack
+        let clause: Vec<String> = (1..=1000).map(|var| var.to_string()).collect();
+        let input = format!("p cnf 1000 1\n{} 0\n", clause.join(" "));
+        let mut state = parse_reader(input.as_bytes()).unwrap();
+        assert_eq!(count(&mut state), (BigUint::from(1u8) << 1000) - 1u8);
+    }
+}
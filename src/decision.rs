@@ -0,0 +1,433 @@
+use std::cmp::{max, min};
+use std::collections::HashMap;
+
+use rand::rngs::StdRng;
+use rand::Rng;
+
+use crate::assignment::Assignment;
+use crate::clause::Clause;
+use crate::config::{Algorithm, Decision, SolverConfig};
+
+mod counting;
+mod lrb;
+mod vmtf;
+
+pub use counting::{Dlcs, Dlis, JeroslowWang, Moms};
+pub use lrb::{Chb, Lrb};
+pub use vmtf::Vmtf;
+
+/// What a heuristic may inspect when choosing a decision literal
+pub struct DecisionContext<'a> {
+    pub assignment: &'a Assignment,
+    pub clauses: &'a [Clause],
+    // True for clauses already satisfied by the assignment
+    pub clause_status: &'a [bool],
+    // The solver's seeded generator, the only source of randomness a heuristic should use
+    pub rng: &'a mut StdRng,
+}
+
+/// Chooses decision literals and observes the search through its hooks.
+///
+/// Only called to decide when at least one clause is unsatisfied and no clause is unit,
+/// so there is always an unassigned literal to return.
+pub trait DecisionHeuristic {
+    fn name(&self) -> &'static str;
+
+    /// Called with the number of variables before the search starts
+    fn init(&mut self, _vars: usize) {}
+
+    /// Called when variables are added between searches, with the new number of variables.
+    /// Heuristics keeping scores or an order should keep them for the existing variables, the
+    /// default starts over.
+    fn grow(&mut self, vars: usize) {
+        self.init(vars);
+    }
+
+    /// Called whenever a literal is put on the trail
+    fn on_assign(&mut self, _literal: i32) {}
+
+    /// Called for every literal removed from the trail by backtracking
+    fn on_unassign(&mut self, _literal: i32) {}
+
+    /// Called once per conflict with the literals conflict analysis holds responsible
+    fn on_conflict(&mut self, _literals: &[i32]) {}
+
+    /// Called once per conflict, before `on_conflict`, with every literal seen during conflict
+    /// analysis and the literals of the reasons for the learned clause that were not seen
+    fn on_analysis(&mut self, _conflict_side: &[i32], _reason_side: &[i32]) {}
+
+    /// Called when rephasing replaces the polarity saved for an unassigned variable. Only
+    /// heuristics that do phase saving take it, the others keep choosing polarities their way.
+    fn save_phase(&mut self, _var: usize, _value: bool) {}
+
+    fn pick_branch_literal(&mut self, ctx: &mut DecisionContext) -> i32;
+}
+
+/// Builds the built-in heuristic selected by the configuration
+pub fn from_config(config: &SolverConfig) -> Box<dyn DecisionHeuristic> {
+    match config.decision {
+        Decision::FirstUnsat => Box::new(FirstUnsat),
+        Decision::Bohm => Box::new(Bohm {
+            alpha: config.bohm_alpha,
+            beta: config.bohm_beta,
+        }),
+        Decision::Activity => Box::new(Activity::new(config)),
+        Decision::Random => Box::new(RandomDecision),
+        Decision::Vmtf => Box::new(Vmtf::new()),
+        Decision::Lrb => Box::new(Lrb::new(config)),
+        Decision::Chb => Box::new(Chb::new(config)),
+        Decision::Jw => Box::new(JeroslowWang { two_sided: false }),
+        Decision::JwTwoSided => Box::new(JeroslowWang { two_sided: true }),
+        Decision::Moms => Box::new(Moms { k: config.moms_k }),
+        Decision::Dlis => Box::new(Dlis),
+        Decision::Dlcs => Box::new(Dlcs),
+    }
+}
+
+/// Picks the first unassigned literal of the first unsatisfied clause
+pub struct FirstUnsat;
+
+impl DecisionHeuristic for FirstUnsat {
+    fn name(&self) -> &'static str {
+        "first-unsat"
+    }
+
+    fn pick_branch_literal(&mut self, ctx: &mut DecisionContext) -> i32 {
+        let (assignment, clause_status, clauses) = (ctx.assignment, ctx.clause_status, ctx.clauses);
+        for index in 0..clause_status.len() {
+            if clause_status[index] {
+                // Skip over sat clauses
+                continue;
+            }
+            let decide_clause = &clauses[index];
+            for var in &decide_clause.vars {
+                let index: usize = var.abs().try_into().unwrap();
+                if assignment[index].is_none() {
+                    return *var;
+                }
+            }
+        }
+        panic!("No unsat clauses to decide on");
+    }
+}
+
+pub struct Bohm {
+    pub alpha: usize,
+    pub beta: usize,
+}
+
+impl DecisionHeuristic for Bohm {
+    fn name(&self) -> &'static str {
+        "bohm"
+    }
+
+    fn pick_branch_literal(&mut self, ctx: &mut DecisionContext) -> i32 {
+        bohm_literal(ctx.assignment, ctx.clause_status, ctx.clauses, self.alpha, self.beta)
+    }
+}
+
+/// Per-literal activity bumped by conflicts and decayed after each one (VSIDS style).
+/// Falls back to Bohm until some literal has gained activity.
+pub struct Activity {
+    activitylist: Vec<f32>,
+    vars: usize,
+    decay: f64,
+    alpha: usize,
+    beta: usize,
+}
+
+impl Activity {
+    pub fn new(config: &SolverConfig) -> Activity {
+        Activity {
+            activitylist: Vec::new(),
+            vars: 0,
+            decay: match config.algorithm {
+                Algorithm::Dpll => config.dpll_decay,
+                _ => config.decay,
+            },
+            alpha: config.bohm_alpha,
+            beta: config.bohm_beta,
+        }
+    }
+
+    fn index(&self, var: i32) -> usize {
+        let var_index: usize = (TryInto::<usize>::try_into((var).abs()).unwrap()) - 1;
+        if var > 0 {
+            var_index
+        } else {
+            var_index + self.vars
+        }
+    }
+
+    pub fn get_activity(&self, var: i32) -> f32 {
+        self.activitylist[self.index(var)]
+    }
+}
+
+impl DecisionHeuristic for Activity {
+    fn name(&self) -> &'static str {
+        "activity"
+    }
+
+    fn init(&mut self, vars: usize) {
+        self.vars = vars;
+        self.activitylist = vec![0.0; vars * 2];
+    }
+
+    fn grow(&mut self, vars: usize) {
+        // Negative literals are stored after all positive ones, so both halves move apart
+        let mut activitylist = vec![0.0; vars * 2];
+        activitylist[..self.vars].copy_from_slice(&self.activitylist[..self.vars]);
+        activitylist[vars..vars + self.vars].copy_from_slice(&self.activitylist[self.vars..]);
+        self.vars = vars;
+        self.activitylist = activitylist;
+    }
+
+    fn on_conflict(&mut self, literals: &[i32]) {
+        for var in literals {
+            let index = self.index(*var);
+            self.activitylist[index] += 1.0;
+        }
+
+        // Decay all activity values
+        for activity in self.activitylist.iter_mut() {
+            *activity *= self.decay as f32;
+        }
+    }
+
+    fn pick_branch_literal(&mut self, ctx: &mut DecisionContext) -> i32 {
+        let mut best_activity = 0.0;
+        let mut best_var: i32 = 0;
+
+        // Check activity of all literals
+        for i in 0..self.vars {
+            let var = <usize as TryInto<i32>>::try_into(i).unwrap() + 1;
+            if ctx.assignment[i + 1].is_some() {
+                continue;
+            }
+            let var_activty = self.get_activity(var);
+            if var_activty > best_activity {
+                best_activity = var_activty;
+                best_var = var;
+            }
+        }
+
+        for i in 0..self.vars {
+            let var = -(<usize as TryInto<i32>>::try_into(i).unwrap() + 1);
+            if ctx.assignment[i + 1].is_some() {
+                continue;
+            }
+            let var_activty = self.get_activity(var);
+            if var_activty > best_activity {
+                best_activity = var_activty;
+                best_var = var
+            }
+        }
+        
+        // If a literal could not be decided on, use bohm
+        // This will only happen as the solver is starting
+        if best_var == 0 {
+            return bohm_literal(ctx.assignment, ctx.clause_status, ctx.clauses, self.alpha, self.beta);
+        }
+        best_var
+    }
+}
+
+/// Uniformly random unassigned variable with a random polarity
+pub struct RandomDecision;
+
+impl DecisionHeuristic for RandomDecision {
+    fn name(&self) -> &'static str {
+        "random"
+    }
+
+    fn pick_branch_literal(&mut self, ctx: &mut DecisionContext) -> i32 {
+        let unassigned: Vec<usize> = (1..=ctx.assignment.len())
+            .filter(|var| ctx.assignment[*var].is_none())
+            .collect();
+        let var = unassigned[ctx.rng.gen_range(0..unassigned.len())] as i32;
+        if ctx.rng.gen::<bool>() {
+            var
+        } else {
+            -var
+        }
+    }
+}
+
+/// Bohm's heuristic: prefers variables occurring often in short unsatisfied clauses
+pub fn bohm_literal(
+    assignment: &Assignment,
+    clause_status: &[bool],
+    clauses: &[Clause],
+    alpha: usize,
+    beta: usize,
+) -> i32 {
+    let mut var_to_count_map_map: HashMap<i32, HashMap<usize, usize>> = HashMap::new();
+    // Generate "Vectors" of counts in clauses of size n
+    let mut max_clause_len = 0;
+    let mut last_var = 0;
+    for index in 0..clauses.len() {
+        if clause_status[index] {
+            continue;
+        }
+        let clause = &clauses[index];
+        let mut adjusted_list: Vec<i32> = Vec::new();
+        for var in &clause.vars {
+            let val = *var;
+            let assignment_index: usize = val.abs().try_into().unwrap();
+            if assignment[assignment_index].is_none() {
+                adjusted_list.push(val);
+                last_var = val;
+            }
+        }
+        let length = adjusted_list.len();
+        max_clause_len = max(max_clause_len, length);
+        for var in adjusted_list {
+            let len_to_count_map = var_to_count_map_map.entry(var).or_default();
+            *len_to_count_map.entry(length).or_insert(0) += 1;
+        }
+    }
+
+    let empty: HashMap<usize, usize> = HashMap::new();
+    // Find best variable to assign
+    let mut best_var = last_var;
+    for index in 1..=assignment.len() {
+        if assignment[index].is_some() {
+            continue;
+        }
+        let best_map = var_to_count_map_map.get(&best_var).unwrap_or(&empty);
+        let best_map_inv = var_to_count_map_map.get(&-best_var).unwrap_or(&empty);
+
+        let cur: i32 = index.try_into().unwrap();
+        let cur_inv: i32 = -cur;
+        let cur_map = var_to_count_map_map.get(&cur).unwrap_or(&empty);
+        let cur_map_inv = var_to_count_map_map.get(&cur_inv).unwrap_or(&empty);
+
+        for len in 1..=max_clause_len {
+            let h_cur = match cur_map.get(&len) {
+                Some(count) => *count,
+                None => 0,
+            };
+            let h_cur_inv = match cur_map_inv.get(&len) {
+                Some(count) => *count,
+                None => 0,
+            };
+            let h_best = match best_map.get(&len) {
+                Some(count) => *count,
+                None => 0,
+            };
+            let h_best_inv = match best_map_inv.get(&len) {
+                Some(count) => *count,
+                None => 0,
+            };
+
+            let score_cur = alpha * max(h_cur, h_cur_inv) + beta * min(h_cur, h_cur_inv);
+            let score_best = alpha * max(h_best, h_best_inv) + beta * min(h_best, h_best_inv);
+            if score_best == score_cur {
+                continue;
+            } else if score_cur > score_best {
+                best_var = cur;
+                break;
+            } else {
+                break;
+            }
+        }
+    }
+
+    // Check if best_var or its inverse is best
+    let mut normal_sum = 0;
+    let mut inv_sum = 0;
+
+    let best_map = var_to_count_map_map.get(&best_var).unwrap_or(&empty);
+    let best_map_inv = var_to_count_map_map.get(&-best_var).unwrap_or(&empty);
+    for i in 1..=max_clause_len {
+        normal_sum += match best_map.get(&i) {
+            Some(count) => *count,
+            None => 0,
+        };
+        inv_sum += match best_map_inv.get(&i) {
+            Some(count) => *count,
+            None => 0,
+        };
+    }
+    if normal_sum > inv_sum {
+        best_var
+    } else {
+        -best_var
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::{Activity, DecisionContext, DecisionHeuristic};
+    use crate::config::SolverConfig;
+    use crate::parser::parse_reader;
+    use crate::solver::solve;
+
+    // Decides the lowest unassigned variable negatively and records the hook calls it sees
+    struct Recording {
+        trail: Rc<RefCell<Vec<i32>>>,
+        conflicts: Rc<RefCell<usize>>,
+    }
+
+    impl DecisionHeuristic for Recording {
+        fn name(&self) -> &'static str {
+            "recording"
+        }
+
+        fn on_assign(&mut self, literal: i32) {
+            self.trail.borrow_mut().push(literal);
+        }
+
+        fn on_unassign(&mut self, literal: i32) {
+            assert_eq!(self.trail.borrow_mut().pop(), Some(literal));
+        }
+
+        fn on_conflict(&mut self, _literals: &[i32]) {
+            *self.conflicts.borrow_mut() += 1;
+        }
+
+        fn pick_branch_literal(&mut self, ctx: &mut DecisionContext) -> i32 {
+            let var = (1..=ctx.assignment.len()).find(|var| ctx.assignment[*var].is_none());
+            -(var.unwrap() as i32)
+        }
+    }
+
+    #[test]
+    fn custom_heuristic_sees_trail() {
+        let input = "p cnf 4 5\n1 2 0\n-1 3 0\n-3 4 0\n-2 -4 0\n2 3 4 0\n";
+        let mut state = parse_reader(input.as_bytes()).unwrap();
+        let trail = Rc::new(RefCell::new(Vec::new()));
+        let conflicts = Rc::new(RefCell::new(0));
+        state.set_heuristic(Box::new(Recording {
+            trail: trail.clone(),
+            conflicts: conflicts.clone(),
+        }));
+
+        assert!(solve(&mut state));
+        assert_eq!(state.heuristic().name(), "recording");
+        let mut expected: Vec<i32> = state.get_movelist().iter().filter_map(|m| m.literal()).collect();
+        let mut seen = trail.borrow().clone();
+        expected.sort();
+        seen.sort();
+        assert_eq!(seen, expected);
+        assert_eq!(*conflicts.borrow(), state.stats().conflicts);
+    }
+
+    #[test]
+    fn growing_keeps_activity() {
+        let mut activity = Activity::new(&SolverConfig::new());
+        activity.init(2);
+        activity.on_conflict(&[1, -2]);
+        activity.grow(4);
+        assert_eq!(activity.get_activity(1), 0.5);
+        assert_eq!(activity.get_activity(-2), 0.5);
+        assert_eq!(activity.get_activity(-1), 0.0);
+        assert_eq!(activity.get_activity(4), 0.0);
+        assert_eq!(activity.get_activity(-4), 0.0);
+    }
+}
@@ -0,0 +1,232 @@
+use super::{DecisionContext, DecisionHeuristic};
+
+/// Sums `weight(length)` over the unsatisfied clauses each literal occurs in, where the length
+/// only counts unassigned literals. Returns the sums for positive and negative literals by variable.
+fn literal_scores(ctx: &DecisionContext, weight: impl Fn(usize) -> f64) -> (Vec<f64>, Vec<f64>) {
+    let mut positive = vec![0.0; ctx.assignment.len() + 1];
+    let mut negative = vec![0.0; ctx.assignment.len() + 1];
+    for (index, clause) in ctx.clauses.iter().enumerate() {
+        if ctx.clause_status[index] {
+            continue;
+        }
+        let unassigned: Vec<i32> = clause
+            .vars
+            .iter()
+            .copied()
+            .filter(|var| ctx.assignment[var.unsigned_abs() as usize].is_none())
+            .collect();
+        let score = weight(unassigned.len());
+        for var in unassigned {
+            if var > 0 {
+                positive[var as usize] += score;
+            } else {
+                negative[var.unsigned_abs() as usize] += score;
+            }
+        }
+    }
+    (positive, negative)
+}
+
+// Unassigned literal with the highest score, preferring lower variables and positive literals on ties
+fn best_literal(ctx: &DecisionContext, positive: &[f64], negative: &[f64]) -> i32 {
+    let mut best: Option<(f64, i32)> = None;
+    for var in 1..positive.len() {
+        if ctx.assignment[var].is_some() {
+            continue;
+        }
+        for (score, literal) in [(positive[var], var as i32), (negative[var], -(var as i32))] {
+            if best.is_none_or(|(best_score, _)| score > best_score) {
+                best = Some((score, literal));
+            }
+        }
+    }
+    best.expect("No unassigned variable to decide on").1
+}
+
+// Unassigned variable with the highest `score(positive, negative)`, in its more frequent polarity
+fn best_variable(
+    ctx: &DecisionContext,
+    positive: &[f64],
+    negative: &[f64],
+    score: impl Fn(f64, f64) -> f64,
+) -> i32 {
+    let mut best: Option<(f64, i32)> = None;
+    for var in 1..positive.len() {
+        if ctx.assignment[var].is_some() {
+            continue;
+        }
+        let var_score = score(positive[var], negative[var]);
+        if best.is_none_or(|(best_score, _)| var_score > best_score) {
+            let literal = if positive[var] >= negative[var] {
+                var as i32
+            } else {
+                -(var as i32)
+            };
+            best = Some((var_score, literal));
+        }
+    }
+    best.expect("No unassigned variable to decide on").1
+}
+
+/// Jeroslow-Wang: each occurrence of a literal in a clause of length n is worth 2^-n.
+/// The one-sided rule picks the best literal, the two-sided rule the best variable.
+pub struct JeroslowWang {
+    pub two_sided: bool,
+}
+
+impl DecisionHeuristic for JeroslowWang {
+    fn name(&self) -> &'static str {
+        if self.two_sided {
+            "jw-two-sided"
+        } else {
+            "jw"
+        }
+    }
+
+    fn pick_branch_literal(&mut self, ctx: &mut DecisionContext) -> i32 {
+        let (positive, negative) = literal_scores(ctx, |len| 2f64.powi(-(len as i32)));
+        if self.two_sided {
+            best_variable(ctx, &positive, &negative, |pos, neg| pos + neg)
+        } else {
+            best_literal(ctx, &positive, &negative)
+        }
+    }
+}
+
+/// Maximum occurrences in clauses of minimum size, scoring variables by
+/// (f(x) + f(-x)) * 2^k + f(x) * f(-x) over the shortest unsatisfied clauses
+pub struct Moms {
+    pub k: i32,
+}
+
+impl DecisionHeuristic for Moms {
+    fn name(&self) -> &'static str {
+        "moms"
+    }
+
+    fn pick_branch_literal(&mut self, ctx: &mut DecisionContext) -> i32 {
+        let mut min_len = usize::MAX;
+        for (index, clause) in ctx.clauses.iter().enumerate() {
+            if ctx.clause_status[index] {
+                continue;
+            }
+            let len = clause
+                .vars
+                .iter()
+                .filter(|var| ctx.assignment[var.unsigned_abs() as usize].is_none())
+                .count();
+            min_len = min_len.min(len);
+        }
+
+        let (positive, negative) =
+            literal_scores(ctx, |len| if len == min_len { 1.0 } else { 0.0 });
+        let factor = 2f64.powi(self.k);
+        best_variable(ctx, &positive, &negative, |pos, neg| (pos + neg) * factor + pos * neg)
+    }
+}
+
+/// Dynamic largest individual sum: the literal occurring in the most unsatisfied clauses
+pub struct Dlis;
+
+impl DecisionHeuristic for Dlis {
+    fn name(&self) -> &'static str {
+        "dlis"
+    }
+
+    fn pick_branch_literal(&mut self, ctx: &mut DecisionContext) -> i32 {
+        let (positive, negative) = literal_scores(ctx, |_| 1.0);
+        best_literal(ctx, &positive, &negative)
+    }
+}
+
+/// Dynamic largest combined sum: the variable occurring in the most unsatisfied clauses
+pub struct Dlcs;
+
+impl DecisionHeuristic for Dlcs {
+    fn name(&self) -> &'static str {
+        "dlcs"
+    }
+
+    fn pick_branch_literal(&mut self, ctx: &mut DecisionContext) -> i32 {
+        let (positive, negative) = literal_scores(ctx, |_| 1.0);
+        best_variable(ctx, &positive, &negative, |pos, neg| pos + neg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::{Dlcs, Dlis, JeroslowWang, Moms};
+    use crate::assignment::Assignment;
+    use crate::clause::{AssignmentResult, Clause};
+    use crate::decision::{DecisionContext, DecisionHeuristic};
+    use crate::solver_state::SolverMove;
+
+    // Built so that every heuristic prefers a different variable:
+    // 3 is the most frequent literal in binary clauses (JW one-sided),
+    // 4 has the highest two-sided JW weight from its ternary clauses,
+    // 6 occurs in both polarities among the binary clauses (MOMS),
+    // 1 is the most frequent literal (DLIS) and 2 the most frequent variable (DLCS)
+    fn formula() -> Vec<Clause> {
+        let mut clauses: Vec<Vec<i32>> = vec![
+            vec![3, 7],
+            vec![3, 8],
+            vec![6, 9],
+            vec![-6, 10],
+            vec![4, 11, 12],
+            vec![4, 13, 14],
+            vec![4, 15, 16],
+            vec![-4, 17, 18],
+            vec![-4, 19, 20],
+        ];
+        let mut filler = 21;
+        for prefix in [[1, 2], [1, 2], [1, 2], [1, 2], [1, -2], [1, -2]] {
+            clauses.push(vec![prefix[0], prefix[1], filler, filler + 1, filler + 2]);
+            filler += 3;
+        }
+        for _ in 0..2 {
+            clauses.push(vec![-2, filler, filler + 1, filler + 2, filler + 3]);
+            filler += 4;
+        }
+        clauses.into_iter().map(Clause::from_vec).collect()
+    }
+
+    fn pick(heuristic: &mut dyn DecisionHeuristic, trail: Vec<SolverMove>) -> i32 {
+        let clauses = formula();
+        let assignment = Assignment::from_movelist(&trail, 46);
+        let clause_status: Vec<bool> = clauses
+            .iter()
+            .map(|clause| clause.check_assignment(&assignment) == Some(AssignmentResult::Sat()))
+            .collect();
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut ctx = DecisionContext {
+            assignment: &assignment,
+            clauses: &clauses,
+            clause_status: &clause_status,
+            rng: &mut rng,
+        };
+        heuristic.pick_branch_literal(&mut ctx)
+    }
+
+    #[test]
+    fn heuristics_differ_on_crafted_formula() {
+        assert_eq!(pick(&mut JeroslowWang { two_sided: false }, vec![]), 3);
+        assert_eq!(pick(&mut JeroslowWang { two_sided: true }, vec![]), 4);
+        assert_eq!(pick(&mut Moms { k: 10 }, vec![]), 6);
+        assert_eq!(pick(&mut Dlis, vec![]), 1);
+        assert_eq!(pick(&mut Dlcs, vec![]), 2);
+    }
+
+    #[test]
+    fn satisfied_clauses_are_ignored() {
+        // With 3 true its binary clauses no longer count, leaving 4 as the best literal
+        assert_eq!(pick(&mut JeroslowWang { two_sided: false }, vec![SolverMove::Decide(3)]), 4);
+        // With 1 true only the clauses containing -2 remain among the long ones
+        assert_eq!(pick(&mut Dlis, vec![SolverMove::Decide(1), SolverMove::Decide(4)]), -2);
+        // Satisfying both clauses on 6 leaves 3 with the most occurrences among binary clauses
+        let trail = vec![SolverMove::Decide(9), SolverMove::Decide(10)];
+        assert_eq!(pick(&mut Moms { k: 10 }, trail), 3);
+    }
+}
@@ -0,0 +1,285 @@
+use super::{DecisionContext, DecisionHeuristic};
+use crate::config::SolverConfig;
+
+// Factor applied to the scores of unassigned variables after each conflict (LRB locality)
+const LOCALITY_DECAY: f64 = 0.95;
+// CHB reward multipliers for assignments that did and did not end in a conflict
+const CHB_CONFLICT_MULTIPLIER: f64 = 1.0;
+const CHB_PROPAGATION_MULTIPLIER: f64 = 0.9;
+
+/// Exponential recency weighted average step size shared by LRB and CHB, starting at `step`
+/// and shrinking by `decay` per conflict down to `min_step`
+struct StepSize {
+    step: f64,
+    min_step: f64,
+    decay: f64,
+}
+
+impl StepSize {
+    fn new(config: &SolverConfig) -> StepSize {
+        StepSize {
+            step: config.lrb_step,
+            min_step: config.lrb_min_step,
+            decay: config.lrb_step_decay,
+        }
+    }
+
+    fn update(&self, score: &mut f64, reward: f64) {
+        *score = (1.0 - self.step) * *score + self.step * reward;
+    }
+
+    fn on_conflict(&mut self) {
+        if self.step > self.min_step {
+            self.step = (self.step - self.decay).max(self.min_step);
+        }
+    }
+}
+
+// Highest scoring unassigned variable, with its saved phase
+fn best_unassigned(score: &[f64], phase: &[bool], ctx: &DecisionContext) -> i32 {
+    let mut best: Option<usize> = None;
+    for var in 1..score.len() {
+        if ctx.assignment[var].is_some() {
+            continue;
+        }
+        if best.is_none_or(|best| score[var] > score[best]) {
+            best = Some(var);
+        }
+    }
+    let var = best.expect("No unassigned variable to decide on");
+    if phase[var] {
+        var as i32
+    } else {
+        -(var as i32)
+    }
+}
+
+/// Learning-rate branching: a variable's score estimates the share of conflicts it took part in
+/// while assigned, with the reason side rate and locality extensions.
+pub struct Lrb {
+    score: Vec<f64>,
+    step: StepSize,
+    conflicts: u64,
+    // Conflict count when each variable was assigned
+    assigned_at: Vec<u64>,
+    participated: Vec<u64>,
+    reasoned: Vec<u64>,
+    assigned: Vec<bool>,
+    phase: Vec<bool>,
+}
+
+impl Lrb {
+    pub fn new(config: &SolverConfig) -> Lrb {
+        Lrb {
+            score: Vec::new(),
+            step: StepSize::new(config),
+            conflicts: 0,
+            assigned_at: Vec::new(),
+            participated: Vec::new(),
+            reasoned: Vec::new(),
+            assigned: Vec::new(),
+            phase: Vec::new(),
+        }
+    }
+
+    pub fn score(&self, var: usize) -> f64 {
+        self.score[var]
+    }
+}
+
+impl DecisionHeuristic for Lrb {
+    fn name(&self) -> &'static str {
+        "lrb"
+    }
+
+    fn init(&mut self, vars: usize) {
+        self.score = vec![0.0; vars + 1];
+        self.assigned_at = vec![0; vars + 1];
+        self.participated = vec![0; vars + 1];
+        self.reasoned = vec![0; vars + 1];
+        self.assigned = vec![false; vars + 1];
+        self.phase = vec![false; vars + 1];
+    }
+
+    fn grow(&mut self, vars: usize) {
+        self.score.resize(vars + 1, 0.0);
+        self.assigned_at.resize(vars + 1, 0);
+        self.participated.resize(vars + 1, 0);
+        self.reasoned.resize(vars + 1, 0);
+        self.assigned.resize(vars + 1, false);
+        self.phase.resize(vars + 1, false);
+    }
+
+    fn save_phase(&mut self, var: usize, value: bool) {
+        self.phase[var] = value;
+    }
+
+    fn on_assign(&mut self, literal: i32) {
+        let var = literal.unsigned_abs() as usize;
+        self.assigned[var] = true;
+        self.assigned_at[var] = self.conflicts;
+        self.participated[var] = 0;
+        self.reasoned[var] = 0;
+    }
+
+    fn on_unassign(&mut self, literal: i32) {
+        let var = literal.unsigned_abs() as usize;
+        self.assigned[var] = false;
+        self.phase[var] = literal > 0;
+        let interval = self.conflicts - self.assigned_at[var];
+        if interval > 0 {
+            let rate = self.participated[var] as f64 / interval as f64;
+            let reason_rate = self.reasoned[var] as f64 / interval as f64;
+            self.step.update(&mut self.score[var], rate + reason_rate);
+        }
+    }
+
+    fn on_analysis(&mut self, conflict_side: &[i32], reason_side: &[i32]) {
+        self.conflicts += 1;
+        for literal in conflict_side {
+            self.participated[literal.unsigned_abs() as usize] += 1;
+        }
+        for literal in reason_side {
+            self.reasoned[literal.unsigned_abs() as usize] += 1;
+        }
+        self.step.on_conflict();
+
+        for var in 1..self.score.len() {
+            if !self.assigned[var] {
+                self.score[var] *= LOCALITY_DECAY;
+            }
+        }
+    }
+
+    fn pick_branch_literal(&mut self, ctx: &mut DecisionContext) -> i32 {
+        best_unassigned(&self.score, &self.phase, ctx)
+    }
+}
+
+/// Conflict history based branching: rewards variables by how recently they took part in a
+/// conflict each time they are assigned, with a larger reward for the conflict side.
+pub struct Chb {
+    score: Vec<f64>,
+    step: StepSize,
+    conflicts: u64,
+    last_conflict: Vec<u64>,
+    phase: Vec<bool>,
+}
+
+impl Chb {
+    pub fn new(config: &SolverConfig) -> Chb {
+        Chb {
+            score: Vec::new(),
+            step: StepSize::new(config),
+            conflicts: 0,
+            last_conflict: Vec::new(),
+            phase: Vec::new(),
+        }
+    }
+
+    pub fn score(&self, var: usize) -> f64 {
+        self.score[var]
+    }
+
+    fn reward(&mut self, var: usize, multiplier: f64) {
+        let reward = multiplier / (self.conflicts - self.last_conflict[var] + 1) as f64;
+        self.step.update(&mut self.score[var], reward);
+    }
+}
+
+impl DecisionHeuristic for Chb {
+    fn name(&self) -> &'static str {
+        "chb"
+    }
+
+    fn init(&mut self, vars: usize) {
+        self.score = vec![0.0; vars + 1];
+        self.last_conflict = vec![0; vars + 1];
+        self.phase = vec![false; vars + 1];
+    }
+
+    fn grow(&mut self, vars: usize) {
+        self.score.resize(vars + 1, 0.0);
+        self.last_conflict.resize(vars + 1, 0);
+        self.phase.resize(vars + 1, false);
+    }
+
+    fn save_phase(&mut self, var: usize, value: bool) {
+        self.phase[var] = value;
+    }
+
+    fn on_assign(&mut self, literal: i32) {
+        self.reward(literal.unsigned_abs() as usize, CHB_PROPAGATION_MULTIPLIER);
+    }
+
+    fn on_unassign(&mut self, literal: i32) {
+        self.phase[literal.unsigned_abs() as usize] = literal > 0;
+    }
+
+    fn on_analysis(&mut self, conflict_side: &[i32], _reason_side: &[i32]) {
+        self.conflicts += 1;
+        for literal in conflict_side {
+            let var = literal.unsigned_abs() as usize;
+            self.last_conflict[var] = self.conflicts;
+            self.reward(var, CHB_CONFLICT_MULTIPLIER);
+        }
+        self.step.on_conflict();
+    }
+
+    fn pick_branch_literal(&mut self, ctx: &mut DecisionContext) -> i32 {
+        best_unassigned(&self.score, &self.phase, ctx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Chb, Lrb};
+    use crate::config::SolverConfig;
+    use crate::decision::DecisionHeuristic;
+
+    #[test]
+    fn lrb_rewards_participation_on_unassign() {
+        let mut lrb = Lrb::new(&SolverConfig::new());
+        lrb.init(3);
+        lrb.on_assign(1);
+        lrb.on_assign(-2);
+        lrb.on_assign(3);
+        lrb.on_analysis(&[1, -2], &[3]);
+        lrb.on_analysis(&[1], &[]);
+
+        // Nothing is learned until the variables leave the trail
+        assert_eq!(lrb.score(1), 0.0);
+        lrb.on_unassign(3);
+        lrb.on_unassign(-2);
+        lrb.on_unassign(1);
+
+        // Rates: var 1 took part in 2/2 conflicts, var 2 in 1/2 and var 3 was reason side in 1/2
+        let step = SolverConfig::new().lrb_step;
+        assert!((lrb.score(1) - step).abs() < 1e-4);
+        assert!((lrb.score(2) - step * 0.5).abs() < 1e-4);
+        assert!((lrb.score(3) - step * 0.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn lrb_decays_unassigned_scores() {
+        let mut lrb = Lrb::new(&SolverConfig::new());
+        lrb.init(2);
+        lrb.on_assign(1);
+        lrb.on_analysis(&[1], &[]);
+        lrb.on_unassign(1);
+        let before = lrb.score(1);
+        lrb.on_assign(2);
+        lrb.on_analysis(&[2], &[]);
+        assert!(lrb.score(1) < before);
+    }
+
+    #[test]
+    fn chb_prefers_conflict_side() {
+        let mut chb = Chb::new(&SolverConfig::new());
+        chb.init(2);
+        chb.on_assign(1);
+        chb.on_assign(2);
+        chb.on_analysis(&[2], &[]);
+        assert!(chb.score(2) > chb.score(1));
+    }
+}
@@ -0,0 +1,242 @@
+use super::{DecisionContext, DecisionHeuristic};
+
+// Marks the absence of a neighbour in the queue, variables are numbered from 1
+const NONE: usize = 0;
+
+/// Variable move-to-front: variables taking part in a conflict are moved to the back of a doubly
+/// linked queue and decisions take the unassigned variable closest to the back.
+///
+/// Every variable behind `search` is assigned, so decisions only walk the queue from there.
+/// Polarities come from the last value each variable was assigned (phase saving).
+pub struct Vmtf {
+    prev: Vec<usize>,
+    next: Vec<usize>,
+    // Time each variable was last enqueued, increasing from the front to the back of the queue
+    stamp: Vec<u64>,
+    first: usize,
+    last: usize,
+    search: usize,
+    clock: u64,
+    assigned: Vec<bool>,
+    phase: Vec<bool>,
+}
+
+impl Vmtf {
+    pub fn new() -> Vmtf {
+        Vmtf {
+            prev: Vec::new(),
+            next: Vec::new(),
+            stamp: Vec::new(),
+            first: NONE,
+            last: NONE,
+            search: NONE,
+            clock: 0,
+            assigned: Vec::new(),
+            phase: Vec::new(),
+        }
+    }
+
+    /// Variables from the front to the back of the queue
+    pub fn queue(&self) -> Vec<usize> {
+        let mut order = Vec::new();
+        let mut var = self.first;
+        while var != NONE {
+            order.push(var);
+            var = self.next[var];
+        }
+        order
+    }
+
+    fn unlink(&mut self, var: usize) {
+        let (prev, next) = (self.prev[var], self.next[var]);
+        if prev == NONE {
+            self.first = next;
+        } else {
+            self.next[prev] = next;
+        }
+        if next == NONE {
+            self.last = prev;
+        } else {
+            self.prev[next] = prev;
+        }
+    }
+
+    fn enqueue(&mut self, var: usize) {
+        self.prev[var] = self.last;
+        self.next[var] = NONE;
+        if self.last == NONE {
+            self.first = var;
+        } else {
+            self.next[self.last] = var;
+        }
+        self.last = var;
+        self.clock += 1;
+        self.stamp[var] = self.clock;
+    }
+
+    fn move_to_front(&mut self, var: usize) {
+        if var == self.last {
+            return;
+        }
+        self.unlink(var);
+        self.enqueue(var);
+        if !self.assigned[var] {
+            self.search = var;
+        }
+    }
+}
+
+impl Default for Vmtf {
+    fn default() -> Self {
+        Vmtf::new()
+    }
+}
+
+impl DecisionHeuristic for Vmtf {
+    fn name(&self) -> &'static str {
+        "vmtf"
+    }
+
+    fn init(&mut self, vars: usize) {
+        *self = Vmtf::new();
+        self.prev = vec![NONE; vars + 1];
+        self.next = vec![NONE; vars + 1];
+        self.stamp = vec![0; vars + 1];
+        self.assigned = vec![false; vars + 1];
+        self.phase = vec![false; vars + 1];
+        // Lower numbered variables end up closest to the back and are decided first
+        for var in (1..=vars).rev() {
+            self.enqueue(var);
+        }
+        self.search = self.last;
+    }
+
+    fn grow(&mut self, vars: usize) {
+        let old = self.prev.len().saturating_sub(1);
+        self.prev.resize(vars + 1, NONE);
+        self.next.resize(vars + 1, NONE);
+        self.stamp.resize(vars + 1, 0);
+        self.assigned.resize(vars + 1, false);
+        self.phase.resize(vars + 1, false);
+        // New variables go to the front, decided after every existing one, with the stamps
+        // renumbered to keep increasing towards the back
+        for var in old + 1..=vars {
+            self.prev[var] = NONE;
+            self.next[var] = self.first;
+            if self.first == NONE {
+                self.last = var;
+            } else {
+                self.prev[self.first] = var;
+            }
+            self.first = var;
+        }
+        self.clock = 0;
+        for var in self.queue() {
+            self.clock += 1;
+            self.stamp[var] = self.clock;
+        }
+        if self.search == NONE {
+            self.search = self.last;
+        }
+    }
+
+    fn on_assign(&mut self, literal: i32) {
+        self.assigned[literal.unsigned_abs() as usize] = true;
+    }
+
+    fn on_unassign(&mut self, literal: i32) {
+        let var = literal.unsigned_abs() as usize;
+        self.assigned[var] = false;
+        self.phase[var] = literal > 0;
+        if self.stamp[var] > self.stamp[self.search] {
+            self.search = var;
+        }
+    }
+
+    fn save_phase(&mut self, var: usize, value: bool) {
+        self.phase[var] = value;
+    }
+
+    fn on_conflict(&mut self, literals: &[i32]) {
+        // Bump in the order the variables already have so their relative order is kept
+        let mut vars: Vec<usize> = literals.iter().map(|lit| lit.unsigned_abs() as usize).collect();
+        vars.sort_by_key(|var| self.stamp[*var]);
+        vars.dedup();
+        for var in vars {
+            self.move_to_front(var);
+        }
+    }
+
+    fn pick_branch_literal(&mut self, ctx: &mut DecisionContext) -> i32 {
+        let mut var = self.search;
+        while ctx.assignment[var].is_some() {
+            var = self.prev[var];
+        }
+        self.search = var;
+        let literal = var as i32;
+        if self.phase[var] {
+            literal
+        } else {
+            -literal
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::Vmtf;
+    use crate::assignment::Assignment;
+    use crate::decision::{DecisionContext, DecisionHeuristic};
+    use crate::solver_state::SolverMove;
+
+    fn pick(vmtf: &mut Vmtf, trail: &[SolverMove], vars: usize) -> i32 {
+        let assignment = Assignment::from_movelist(&trail.to_vec(), vars);
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut ctx = DecisionContext {
+            assignment: &assignment,
+            clauses: &[],
+            clause_status: &[],
+            rng: &mut rng,
+        };
+        vmtf.pick_branch_literal(&mut ctx)
+    }
+
+    #[test]
+    fn bumped_variables_move_to_back_in_order() {
+        let mut vmtf = Vmtf::new();
+        vmtf.init(5);
+        assert_eq!(vmtf.queue(), vec![5, 4, 3, 2, 1]);
+
+        vmtf.on_conflict(&[-2, 4, 2]);
+        assert_eq!(vmtf.queue(), vec![5, 3, 1, 4, 2]);
+        assert_eq!(pick(&mut vmtf, &[], 5), -2);
+    }
+
+    #[test]
+    fn growing_keeps_the_queue() {
+        let mut vmtf = Vmtf::new();
+        vmtf.init(3);
+        vmtf.on_conflict(&[3]);
+        vmtf.grow(5);
+        assert_eq!(vmtf.queue(), vec![5, 4, 2, 1, 3]);
+        assert_eq!(pick(&mut vmtf, &[], 5), -3);
+    }
+
+    #[test]
+    fn search_skips_assigned_and_returns_after_backtrack() {
+        let mut vmtf = Vmtf::new();
+        vmtf.init(3);
+        let trail = vec![SolverMove::Decide(1), SolverMove::Decide(-2)];
+        vmtf.on_assign(1);
+        vmtf.on_assign(-2);
+        assert_eq!(pick(&mut vmtf, &trail, 3), -3);
+
+        // Unassigning restores the search position and saves the phase
+        vmtf.on_unassign(-2);
+        vmtf.on_unassign(1);
+        assert_eq!(pick(&mut vmtf, &[], 3), 1);
+    }
+}
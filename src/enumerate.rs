@@ -0,0 +1,162 @@
+use num_bigint::BigUint;
+
+use crate::clause::Clause;
+use crate::solver::solve;
+use crate::solver_state::SolverState;
+
+/// Iterator over the models of a formula, projected onto its independent support if it has one.
+///
+/// Every search ends in an assignment under which all clauses are satisfied, which covers every
+/// combination of the projection variables it left unassigned. These are yielded one by one
+/// before a blocking clause over the assigned projection variables rules them out for the next
+/// search, so a formula with many free variables does not take one search per model.
+///
+/// The formula must not have been preprocessed by passes that change its models, see
+/// `SolverConfig::preserve_models`.
+pub struct Models<'a> {
+    state: &'a mut SolverState,
+    // Variables models are projected onto, in increasing order
+    projection: Vec<usize>,
+    // Projection literals fixed by the last search and the projection variables it left free
+    cube: Vec<i32>,
+    free: Vec<usize>,
+    // Values of the free variables in the next model, None once all were yielded
+    counter: Option<Vec<bool>>,
+    exhausted: bool,
+}
+
+/// Enumerates the models of `state`, which is left with a blocking clause for every search
+pub fn models(state: &mut SolverState) -> Models<'_> {
+    let projection = match state.independent_support() {
+        Some(support) => support.iter().copied().filter(|var| *var <= state.vars()).collect(),
+        None => (1..=state.vars()).collect(),
+    };
+    Models {
+        state,
+        projection,
+        cube: Vec::new(),
+        free: Vec::new(),
+        counter: None,
+        exhausted: false,
+    }
+}
+
+impl Models<'_> {
+    /// Number of models left, adding up the combinations of the free projection variables of
+    /// each search instead of listing them
+    pub fn count_remaining(mut self) -> BigUint {
+        let mut count = BigUint::from(0u8);
+        if let Some(counter) = self.counter.take() {
+            // Combinations of the last search not yielded yet, read from the counter in binary
+            let yielded = counter.iter().rev().fold(BigUint::from(0u8), |sum, value| sum * 2u8 + *value as u8);
+            count += (BigUint::from(1u8) << self.free.len()) - yielded;
+        }
+        while self.search() {
+            count += BigUint::from(1u8) << self.free.len();
+        }
+        count
+    }
+
+    // Runs the next search and blocks the projection of its model, false once none is left
+    fn search(&mut self) -> bool {
+        if self.exhausted || !solve(self.state) {
+            self.exhausted = true;
+            return false;
+        }
+
+        let assignment = self.state.model();
+        self.cube.clear();
+        self.free.clear();
+        for var in &self.projection {
+            match assignment[*var] {
+                Some(value) => self.cube.push(if value { *var as i32 } else { -(*var as i32) }),
+                None => self.free.push(*var),
+            }
+        }
+        if self.cube.is_empty() {
+            // Every assignment of the projection variables is a model
+            self.exhausted = true;
+        } else {
+            let blocking = self.cube.iter().map(|literal| -literal).collect();
+            self.state.add_original_clause(Clause::from_vec(blocking));
+        }
+        true
+    }
+}
+
+// Moves to the next combination of values by counting in binary, false after the last one
+fn advance(counter: &mut [bool]) -> bool {
+    for value in counter.iter_mut() {
+        *value = !*value;
+        if *value {
+            return true;
+        }
+    }
+    false
+}
+
+impl Iterator for Models<'_> {
+    type Item = Vec<i32>;
+
+    fn next(&mut self) -> Option<Vec<i32>> {
+        loop {
+            if let Some(counter) = &mut self.counter {
+                let mut model = self.cube.clone();
+                for (var, value) in self.free.iter().zip(counter.iter()) {
+                    let literal = *var as i32;
+                    model.push(if *value { literal } else { -literal });
+                }
+                model.sort_by_key(|literal| literal.unsigned_abs());
+                if !advance(counter) {
+                    self.counter = None;
+                }
+                return Some(model);
+            }
+            if !self.search() {
+                return None;
+            }
+            self.counter = Some(vec![false; self.free.len()]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num_bigint::BigUint;
+
+    use super::models;
+    use crate::parser::parse_reader;
+    use crate::testutil::brute_force_models;
+
+    #[test]
+    fn all_models_are_found_once() {
+        // Variable 5 occurs in no clause, so half of the models come from free variables
+        let input = "p cnf 5 4\n1 2 0\n-1 3 0\n-2 -3 4 0\n2 -4 0\n";
+        let mut state = parse_reader(input.as_bytes()).unwrap();
+        let mut found: Vec<Vec<i32>> = models(&mut state).collect();
+        found.sort();
+        let mut expected = brute_force_models(&parse_reader(input.as_bytes()).unwrap());
+        expected.sort();
+        assert_eq!(found, expected);
+
+        let mut state = parse_reader(input.as_bytes()).unwrap();
+        let mut iter = models(&mut state);
+        assert_eq!(iter.by_ref().take(3).count(), 3);
+        assert_eq!(iter.count_remaining(), BigUint::from(expected.len() - 3));
+    }
+
+    #[test]
+    fn models_are_projected_onto_independent_support() {
+        let input = "c ind 1 2 0\np cnf 4 3\n1 2 3 0\n-3 4 0\n-1 -2 0\n";
+        let mut state = parse_reader(input.as_bytes()).unwrap();
+        assert_eq!(state.independent_support(), Some(&[1, 2][..]));
+        let mut found: Vec<Vec<i32>> = models(&mut state).collect();
+        found.sort();
+        // (1 2) is excluded by (-1 -2), the others extend to models through 3 and 4
+        assert_eq!(found, vec![vec![-1, -2], vec![-1, 2], vec![1, -2]]);
+
+        let unsat = "p cnf 1 2\n1 0\n-1 0\n";
+        let mut state = parse_reader(unsat.as_bytes()).unwrap();
+        assert_eq!(models(&mut state).next(), None);
+    }
+}
@@ -0,0 +1,327 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::clause::Clause;
+use crate::preprocess::{check_subsumption, lit_index, Subsumption};
+use crate::solver_state::SolverState;
+
+/// Deletes learned clauses subsumed by another clause and strengthens learned clauses by
+/// self-subsuming resolution. The search restarts first, undoing every decision, so no
+/// shortened clause is left unit or false under assignments it never propagated, and no clause
+/// is used to simplify others after being deleted. The restart is counted in the statistics.
+pub fn subsume_learned(state: &mut SolverState) {
+    state.restart();
+    let mut occurs: BTreeMap<i32, Vec<usize>> = BTreeMap::new();
+    for (index, clause) in state.clauselist().iter().enumerate() {
+        for literal in &clause.vars {
+            occurs.entry(*literal).or_default().push(index);
+        }
+    }
+
+    let mut subsumed = BTreeSet::new();
+    let mut strengthened = 0;
+    for index in state.find_deletable_clauses() {
+        let mut clause = state.clauselist()[index].vars.clone();
+        // Any clause simplifying this one contains one of its variables
+        let mut candidates: BTreeSet<usize> = BTreeSet::new();
+        for literal in &clause {
+            for polarity in [*literal, -literal] {
+                candidates.extend(occurs.get(&polarity).into_iter().flatten());
+            }
+        }
+
+        for other in candidates {
+            if other == index || subsumed.contains(&other) {
+                continue;
+            }
+            match check_subsumption(&state.clauselist()[other].vars, &clause) {
+                Some(Subsumption::Subsumed) => {
+                    subsumed.insert(index);
+                    break;
+                }
+                Some(Subsumption::Strengthened(literal)) => {
+                    state.strengthen_clause(index, literal);
+                    clause.retain(|other| *other != literal);
+                    strengthened += 1;
+                }
+                None => (),
+            }
+        }
+    }
+
+    let stats = state.stats_mut();
+    stats.subsumed_clauses += subsumed.len();
+    stats.strengthened_literals += strengthened;
+    state.forget_clause(&subsumed);
+}
+
+/// Unit propagation over the clause database that leaves the trail alone, for vivification
+struct Propagator<'a> {
+    clauses: &'a [Clause],
+    // Clauses containing each literal, by `lit_index`
+    occurs: Vec<Vec<usize>>,
+    value: Vec<Option<bool>>,
+    trail: Vec<i32>,
+    next: usize,
+    // Clauses propagation skips: the one being vivified and the ones deleted so far
+    ignored: BTreeSet<usize>,
+    propagations: usize,
+}
+
+impl<'a> Propagator<'a> {
+    // Starts from the unit clauses, or returns None if they already conflict
+    fn new(clauses: &'a [Clause], vars: usize) -> Option<Propagator<'a>> {
+        let mut occurs = vec![Vec::new(); 2 * vars + 2];
+        for (index, clause) in clauses.iter().enumerate() {
+            for literal in &clause.vars {
+                occurs[lit_index(*literal)].push(index);
+            }
+        }
+        let mut propagator = Propagator {
+            clauses,
+            occurs,
+            value: vec![None; vars + 1],
+            trail: Vec::new(),
+            next: 0,
+            ignored: BTreeSet::new(),
+            propagations: 0,
+        };
+        for clause in clauses {
+            match clause.vars[..] {
+                [] => return None,
+                [unit] if !propagator.assign(unit) => return None,
+                _ => (),
+            }
+        }
+        propagator.propagate().then_some(propagator)
+    }
+
+    fn value(&self, literal: i32) -> Option<bool> {
+        self.value[literal.unsigned_abs() as usize].map(|val| val == (literal > 0))
+    }
+
+    // Makes `literal` true, returning false if it already is false
+    fn assign(&mut self, literal: i32) -> bool {
+        match self.value(literal) {
+            Some(val) => val,
+            None => {
+                self.value[literal.unsigned_abs() as usize] = Some(literal > 0);
+                self.trail.push(literal);
+                true
+            }
+        }
+    }
+
+    // Returns false on a conflict
+    fn propagate(&mut self) -> bool {
+        while self.next < self.trail.len() {
+            let literal = self.trail[self.next];
+            self.next += 1;
+            for index in &self.occurs[lit_index(-literal)] {
+                if self.ignored.contains(index) {
+                    continue;
+                }
+                let mut open = self.clauses[*index]
+                    .vars
+                    .iter()
+                    .filter(|other| self.value(**other) != Some(false));
+                match (open.next(), open.next()) {
+                    (None, _) => return false,
+                    (Some(implied), None) if self.value(*implied).is_none() => {
+                        let implied = *implied;
+                        self.value[implied.unsigned_abs() as usize] = Some(implied > 0);
+                        self.trail.push(implied);
+                        self.propagations += 1;
+                    }
+                    _ => (),
+                }
+            }
+        }
+        true
+    }
+
+    fn backtrack(&mut self, len: usize) {
+        for literal in self.trail.drain(len..) {
+            self.value[literal.unsigned_abs() as usize] = None;
+        }
+        self.next = len;
+    }
+
+    /// Assumes the negations of the literals of a clause one at a time, keeping the literals
+    /// that were needed. Returns the literals the clause can be shortened to and whether the
+    /// clause is implied by the others, or None for clauses touching the unit clauses.
+    fn vivify(&mut self, index: usize) -> Option<(Vec<i32>, bool)> {
+        let clause = &self.clauses[index].vars;
+        if clause.iter().any(|literal| self.value(*literal).is_some()) {
+            return None;
+        }
+        self.ignored.insert(index);
+        let root = self.trail.len();
+        let mut kept = Vec::new();
+        let mut implied = false;
+        for literal in clause {
+            match self.value(*literal) {
+                // Implied by the negations of the literals kept so far
+                Some(true) => {
+                    kept.push(*literal);
+                    implied = true;
+                    break;
+                }
+                // The negations of the literals kept so far already make it false
+                Some(false) => continue,
+                None => {
+                    kept.push(*literal);
+                    self.assign(-literal);
+                    if !self.propagate() {
+                        implied = true;
+                        break;
+                    }
+                }
+            }
+        }
+        self.backtrack(root);
+        self.ignored.remove(&index);
+        Some((kept, implied))
+    }
+}
+
+/// Vivification: shortens clauses to the literals whose negations are needed to propagate a
+/// conflict or another of their literals, and deletes learned clauses implied by the others.
+/// Learned clauses are tried newest first, then original clauses, while the propagations spent
+/// on vivification stay within `vivify-effort` times the search propagations. Unless the budget
+/// is spent, the search restarts first, undoing every decision, so vivification only shortens
+/// clauses at level 0. The restart is counted in the statistics.
+pub fn vivify(state: &mut SolverState) {
+    let budget = (state.config().vivify_effort * state.stats().propagations as f64) as usize;
+    let budget = budget.saturating_sub(state.stats().vivify_propagations);
+    if budget == 0 {
+        return;
+    }
+
+    state.restart();
+    let original = state.original_clause_count;
+    let candidates: Vec<usize> = (original..state.clauses())
+        .rev()
+        .chain(0..original)
+        .filter(|index| state.clauselist()[*index].vars.len() > 1)
+        .collect();
+
+    let mut shortened: Vec<(usize, Vec<i32>)> = Vec::new();
+    let mut deleted = BTreeSet::new();
+    let propagations = {
+        let mut propagator = match Propagator::new(state.clauselist(), state.vars()) {
+            Some(propagator) => propagator,
+            // The unit clauses conflict, which the search finds right away
+            None => return,
+        };
+        for index in candidates {
+            if propagator.propagations >= budget {
+                break;
+            }
+            let Some((kept, implied)) = propagator.vivify(index) else {
+                continue;
+            };
+            if kept.len() < propagator.clauses[index].vars.len() {
+                shortened.push((index, kept));
+            } else if implied && index >= original {
+                deleted.insert(index);
+                // Two clauses implying each other must not both be deleted
+                propagator.ignored.insert(index);
+            }
+        }
+        propagator.propagations
+    };
+
+    let mut removed_literals = 0;
+    for (index, kept) in &shortened {
+        let removed: Vec<i32> = state.clauselist()[*index]
+            .vars
+            .iter()
+            .copied()
+            .filter(|literal| !kept.contains(literal))
+            .collect();
+        removed_literals += removed.len();
+        for literal in removed {
+            state.strengthen_clause(*index, literal);
+        }
+    }
+    let stats = state.stats_mut();
+    stats.vivify_propagations += propagations;
+    stats.vivified_clauses += shortened.len() + deleted.len();
+    stats.vivified_literals += removed_literals;
+    state.forget_clause(&deleted);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{subsume_learned, vivify};
+    use crate::clause::Clause;
+    use crate::parser::parse_reader;
+    use crate::solver_state::SolverMove;
+
+    #[test]
+    fn learned_clauses_are_simplified() {
+        let input = "p cnf 4 2\n1 2 0\n-3 4 0\n";
+        let mut state = parse_reader(input.as_bytes()).unwrap();
+        // Subsumed by (1 2), and strengthened by (-3 4) into a duplicate of the last clause
+        state.add_clause(Clause::from_vec(vec![1, 2, 4]));
+        state.add_clause(Clause::from_vec(vec![3, 4, 1]));
+        state.add_clause(Clause::from_vec(vec![4, 1]));
+
+        subsume_learned(&mut state);
+        let clauses: Vec<Vec<i32>> = state.clauselist().iter().map(|clause| clause.vars.clone()).collect();
+        assert_eq!(clauses, vec![vec![1, 2], vec![-3, 4], vec![4, 1]]);
+        assert_eq!(state.stats().subsumed_clauses, 2);
+        assert_eq!(state.stats().strengthened_literals, 1);
+    }
+
+    #[test]
+    fn subsumption_starts_from_level_zero() {
+        // Under -1 the strengthened learned clause (2) would be unit without being propagated
+        let input = "p cnf 2 1\n-1 2 0\n";
+        let mut state = parse_reader(input.as_bytes()).unwrap();
+        state.add_clause(Clause::from_vec(vec![1, 2]));
+        state.add_decision_lv();
+        state.add_move(SolverMove::Decide(-1));
+
+        subsume_learned(&mut state);
+        assert_eq!(state.clauselist()[1].vars, vec![2]);
+        assert!(state.get_movelist().is_empty());
+        assert_eq!(state.stats().restarts, 1);
+    }
+
+    #[test]
+    fn vivification_shortens_and_deletes() {
+        // Assuming -1 propagates both 3 and -3, so every clause with 1 shortens to (1),
+        // and the learned (-2 5 -4) is implied by (-4 5)
+        let input = "p cnf 5 3\n1 3 0\n1 -3 0\n-4 5 0\n";
+        let mut state = parse_reader(input.as_bytes()).unwrap();
+        state.add_clause(Clause::from_vec(vec![1, 2, 4]));
+        state.add_clause(Clause::from_vec(vec![-2, 5, -4]));
+        let mut config = state.config().clone();
+        config.vivify_effort = 1.0;
+        state.set_config(config);
+        state.stats_mut().propagations = 100;
+
+        vivify(&mut state);
+        let clauses: Vec<Vec<i32>> = state.clauselist().iter().map(|clause| clause.vars.clone()).collect();
+        assert_eq!(clauses, vec![vec![1], vec![1], vec![-4, 5], vec![1]]);
+        assert_eq!(state.stats().vivified_clauses, 4);
+        assert_eq!(state.stats().vivified_literals, 4);
+        assert!(state.stats().vivify_propagations > 0);
+    }
+
+    #[test]
+    fn vivification_starts_from_level_zero() {
+        let input = "p cnf 3 2\n1 3 0\n1 -3 0\n";
+        let mut state = parse_reader(input.as_bytes()).unwrap();
+        state.add_clause(Clause::from_vec(vec![1, 2]));
+        state.add_decision_lv();
+        state.add_move(SolverMove::Decide(-2));
+        state.stats_mut().propagations = 100;
+
+        vivify(&mut state);
+        assert_eq!(state.clauselist()[2].vars, vec![1]);
+        assert!(state.get_movelist().is_empty());
+        assert_eq!(state.stats().restarts, 1);
+    }
+}
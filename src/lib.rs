@@ -0,0 +1,24 @@
+pub mod approxmc;
+pub mod assignment;
+pub mod clause;
+pub mod config;
+pub mod count;
+pub mod decision;
+pub mod enumerate;
+pub mod inprocess;
+pub mod local_search;
+pub mod maxsat;
+pub mod mcs;
+pub mod mus;
+pub mod parser;
+pub mod pb;
+pub mod preprocess;
+pub mod reconstruction;
+pub mod report;
+pub mod simplify;
+pub mod solver;
+pub mod solver_state;
+pub mod statistics;
+#[cfg(test)]
+mod testutil;
+pub mod xor;
@@ -0,0 +1,278 @@
+use rand::rngs::StdRng;
+use rand::Rng;
+
+use crate::assignment::Assignment;
+use crate::clause::Clause;
+use crate::config::{SlsMethod, SolverConfig};
+use crate::preprocess::lit_index;
+use crate::solver_state::SolverState;
+
+/// Best assignment found by a local search run
+pub struct SearchResult {
+    pub assignment: Assignment,
+    // Clauses the assignment leaves unsatisfied, 0 if it is a model
+    pub unsatisfied: usize,
+    pub flips: usize,
+}
+
+/// WalkSAT and ProbSAT over a fixed set of clauses: every step flips a variable of a randomly
+/// chosen unsatisfied clause, picked by how many clauses the flip would break
+pub struct LocalSearch<'a> {
+    clauses: &'a [Clause],
+    // Clauses containing each literal, by `lit_index`
+    occurs: Vec<Vec<usize>>,
+    // Current value of each variable, index 0 unused
+    values: Vec<bool>,
+    true_count: Vec<usize>,
+    unsat: Vec<usize>,
+    // Position of each clause in `unsat` while it is in there
+    position: Vec<usize>,
+    // Empty clauses, which no assignment satisfies
+    empty: usize,
+    method: SlsMethod,
+    noise: f64,
+    cb: f64,
+}
+
+impl<'a> LocalSearch<'a> {
+    /// Starts from `values`, the initial value of every variable with index 0 unused
+    pub fn new(clauses: &'a [Clause], values: Vec<bool>, config: &SolverConfig) -> LocalSearch<'a> {
+        let mut occurs = vec![Vec::new(); 2 * values.len()];
+        for (index, clause) in clauses.iter().enumerate() {
+            for literal in &clause.vars {
+                occurs[lit_index(*literal)].push(index);
+            }
+        }
+        let mut search = LocalSearch {
+            clauses,
+            occurs,
+            values,
+            true_count: vec![0; clauses.len()],
+            unsat: Vec::new(),
+            position: vec![0; clauses.len()],
+            empty: 0,
+            method: config.sls,
+            noise: config.walksat_noise,
+            cb: config.probsat_cb,
+        };
+        for (index, clause) in clauses.iter().enumerate() {
+            let count = clause.vars.iter().filter(|literal| search.is_true(**literal)).count();
+            search.true_count[index] = count;
+            if clause.vars.is_empty() {
+                search.empty += 1;
+            } else if count == 0 {
+                search.position[index] = search.unsat.len();
+                search.unsat.push(index);
+            }
+        }
+        search
+    }
+
+    fn is_true(&self, literal: i32) -> bool {
+        self.values[literal.unsigned_abs() as usize] == (literal > 0)
+    }
+
+    /// Number of clauses currently unsatisfied
+    pub fn unsatisfied(&self) -> usize {
+        self.unsat.len() + self.empty
+    }
+
+    // Clauses that flipping `var` would leave without a true literal
+    fn break_value(&self, var: usize) -> usize {
+        let literal = if self.values[var] { var as i32 } else { -(var as i32) };
+        self.occurs[lit_index(literal)]
+            .iter()
+            .filter(|index| self.true_count[**index] == 1)
+            .count()
+    }
+
+    fn flip(&mut self, var: usize) {
+        let old = if self.values[var] { var as i32 } else { -(var as i32) };
+        self.values[var] = !self.values[var];
+        for index in &self.occurs[lit_index(old)] {
+            self.true_count[*index] -= 1;
+            if self.true_count[*index] == 0 {
+                self.position[*index] = self.unsat.len();
+                self.unsat.push(*index);
+            }
+        }
+        for index in &self.occurs[lit_index(-old)] {
+            self.true_count[*index] += 1;
+            if self.true_count[*index] == 1 {
+                let position = self.position[*index];
+                self.unsat.swap_remove(position);
+                if let Some(moved) = self.unsat.get(position) {
+                    self.position[*moved] = position;
+                }
+            }
+        }
+    }
+
+    // Variable of `clause` to flip next
+    fn pick(&self, clause: &[i32], rng: &mut StdRng) -> usize {
+        let breaks: Vec<usize> = clause
+            .iter()
+            .map(|literal| self.break_value(literal.unsigned_abs() as usize))
+            .collect();
+        let position = match self.method {
+            SlsMethod::Walksat => {
+                let best = *breaks.iter().min().unwrap();
+                if best > 0 && rng.gen::<f64>() < self.noise {
+                    rng.gen_range(0..clause.len())
+                } else {
+                    let ties: Vec<usize> = (0..clause.len()).filter(|i| breaks[*i] == best).collect();
+                    ties[rng.gen_range(0..ties.len())]
+                }
+            }
+            SlsMethod::Probsat => {
+                let weights: Vec<f64> = breaks.iter().map(|b| self.cb.powi(-(*b as i32))).collect();
+                let mut target = rng.gen::<f64>() * weights.iter().sum::<f64>();
+                let mut position = clause.len() - 1;
+                for (i, weight) in weights.iter().enumerate() {
+                    if target < *weight {
+                        position = i;
+                        break;
+                    }
+                    target -= weight;
+                }
+                position
+            }
+        };
+        clause[position].unsigned_abs() as usize
+    }
+
+    /// Flips until every clause is satisfied or `max_flips` flips were made, returning the
+    /// assignment that left the fewest clauses unsatisfied
+    pub fn run(&mut self, max_flips: usize, rng: &mut StdRng) -> SearchResult {
+        let mut best = self.values.clone();
+        let mut best_unsatisfied = self.unsatisfied();
+        let mut flips = 0;
+        while !self.unsat.is_empty() && flips < max_flips {
+            let clauses = self.clauses;
+            let clause = &clauses[self.unsat[rng.gen_range(0..self.unsat.len())]].vars;
+            let var = self.pick(clause, rng);
+            self.flip(var);
+            flips += 1;
+            if self.unsatisfied() < best_unsatisfied {
+                best_unsatisfied = self.unsatisfied();
+                best.clone_from(&self.values);
+            }
+        }
+        let literals: Vec<i32> = (1..best.len())
+            .map(|var| if best[var] { var as i32 } else { -(var as i32) })
+            .collect();
+        SearchResult {
+            assignment: Assignment::from_literals(&literals, best.len() - 1),
+            unsatisfied: best_unsatisfied,
+            flips,
+        }
+    }
+}
+
+// Runs local search over the original clauses of `state` from the given initial values
+fn search_from(state: &mut SolverState, values: Vec<bool>, max_flips: usize) -> SearchResult {
+    let (clauses, config, rng) = state.original_clauses_with_rng();
+    let result = LocalSearch::new(clauses, values, config).run(max_flips, rng);
+    state.stats_mut().flips += result.flips;
+    result
+}
+
+/// Local search from a random assignment over the clauses of `state` for `sls-flips` flips,
+/// as run by `--algorithm=sls`. Learned clauses are left out, as they follow from the others.
+pub fn search(state: &mut SolverState) -> SearchResult {
+    let values = (0..=state.vars()).map(|_| state.rng().gen::<bool>()).collect();
+    let max_flips = state.config().sls_flips;
+    search_from(state, values, max_flips)
+}
+
+/// Runs `rephase-flips` flips of local search starting from the current phases, or the current
+/// trail for variables without one, and makes the best assignment found the new phases. The
+/// search restarts first so that no assigned variable saves its old phase over the new one.
+pub fn rephase(state: &mut SolverState) {
+    let trail = Assignment::from_movelist(&state.get_movelist(), state.vars());
+    let known: Vec<Option<bool>> =
+        (0..=state.vars()).map(|var| state.phase(var).or_else(|| (var > 0).then(|| trail[var]).flatten())).collect();
+    let values = known.into_iter().map(|value| value.unwrap_or_else(|| state.rng().gen::<bool>())).collect();
+    let max_flips = state.config().rephase_flips;
+    let result = search_from(state, values, max_flips);
+    state.restart();
+    state.set_phases(&result.assignment);
+    state.stats_mut().rephases += 1;
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::{rephase, search, LocalSearch};
+    use crate::assignment::Assignment;
+    use crate::config::{Decision, SlsMethod, SolverConfig};
+    use crate::parser::parse_reader;
+    use crate::solver::solve;
+
+    #[test]
+    fn both_methods_find_models() {
+        let input = "p cnf 5 7\n1 2 0\n-1 3 0\n-3 -2 0\n2 4 5 0\n-4 -5 0\n-1 -4 0\n3 5 0\n";
+        for method in [SlsMethod::Walksat, SlsMethod::Probsat] {
+            let mut config = SolverConfig::new();
+            config.sls = method;
+            let mut state = parse_reader(input.as_bytes()).unwrap();
+            state.set_config(config);
+            let result = search(&mut state);
+            assert_eq!(result.unsatisfied, 0);
+            for clause in state.clauselist() {
+                assert!(clause.vars.iter().any(|literal| result.assignment.value(*literal) == Some(true)));
+            }
+            assert_eq!(state.stats().flips, result.flips);
+        }
+    }
+
+    #[test]
+    fn best_assignment_of_unsatisfiable_formula() {
+        // Every assignment of two variables falsifies exactly one clause
+        let input = "p cnf 2 4\n1 2 0\n-1 -2 0\n1 -2 0\n-1 2 0\n";
+        let state = parse_reader(input.as_bytes()).unwrap();
+        let config = SolverConfig::new();
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut local_search = LocalSearch::new(state.clauselist(), vec![false; 3], &config);
+        assert_eq!(local_search.unsatisfied(), 1);
+        let result = local_search.run(100, &mut rng);
+        assert_eq!(result.unsatisfied, 1);
+        assert_eq!(result.flips, 100);
+        assert_eq!(result.assignment.literals(), vec![-1, -2]);
+    }
+
+    #[test]
+    fn rephasing_guides_decisions() {
+        let input = "p cnf 4 4\n1 2 0\n-1 -2 0\n3 4 0\n-3 -4 0\n";
+        let mut state = parse_reader(input.as_bytes()).unwrap();
+        // VMTF does phase saving, so it takes the phases rephasing sets
+        let mut config = state.config().clone();
+        config.decision = Decision::Vmtf;
+        state.set_config(config);
+        rephase(&mut state);
+        assert_eq!(state.stats().rephases, 1);
+        assert_eq!(state.stats().restarts, 1);
+        let phases: Vec<bool> = (1..=4).map(|var| state.phase(var).unwrap()).collect();
+        assert!(solve(&mut state));
+        // The phases already satisfy the formula, so every decision follows them
+        let model = state.model();
+        assert_eq!(state.stats().conflicts, 0);
+        for var in 1..=4 {
+            assert_eq!(model[var], Some(phases[var - 1]));
+        }
+    }
+
+    #[test]
+    fn phases_leave_other_heuristics_alone() {
+        let input = "p cnf 2 2\n1 2 0\n-1 -2 0\n";
+        let mut state = parse_reader(input.as_bytes()).unwrap();
+        let mut config = state.config().clone();
+        config.decision = Decision::FirstUnsat;
+        state.set_config(config);
+        state.set_phases(&Assignment::from_literals(&[-1, -2], 2));
+        let assignment = Assignment::from_literals(&[], 2);
+        assert_eq!(state.pick_branch_literal(&assignment, &[false, false]), 1);
+    }
+}
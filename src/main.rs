@@ -1,341 +1,351 @@
-use std::cmp::{max, min};
-use std::collections::HashMap;
 use std::env;
-use std::error::Error;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-
-use assignment::Assignment;
-use clause::{AssignmentResult, Clause};
-use solver_state::SolverState;
-
-use crate::solver_state::SolverMove;
 use std::time::Instant;
-mod assignment;
-mod clause;
-mod solver_state;
+
+use iron_sat::approxmc;
+use iron_sat::config::{Algorithm, SolverConfig};
+use iron_sat::count;
+use iron_sat::enumerate;
+use iron_sat::local_search;
+use iron_sat::maxsat::solve_maxsat;
+use iron_sat::mcs;
+use iron_sat::mus;
+use iron_sat::parser::parse_input;
+use iron_sat::preprocess::preprocess;
+use iron_sat::report::{self, PhaseTimes};
+use iron_sat::simplify::{reconstruct_file, simplify_file};
+use iron_sat::solver::solve;
+
+const USAGE: &str = "Usage: iron_sat [--preset=<default|sat|unsat>] [--config=<file>] [--<option>=<value>...] [--json] [--all | --max-models=<n>] <file>
+       iron_sat count [--<option>=<value>...] [--json] <file>
+       iron_sat <file.wcnf | file.opb> [--maxsat=<oll|linear>] [--<option>=<value>...] [--json]
+       iron_sat approxmc [--epsilon=<e>] [--delta=<d>] [--<option>=<value>...] [--json] <file>
+       iron_sat mus [--<option>=<value>...] [--json] <file | file.gcnf>
+       iron_sat mcs [--<option>=<value>...] [--json] <file | file.gcnf>
+       iron_sat simplify [--<option>=<value>...] <file> <simplified> <reconstruction>
+       iron_sat reconstruct <reconstruction> <model>";
 
 fn main() {
     let args: Vec<String> = env::args().collect();
 
-    let file = &args[1];
-    println!("Looking for file: {file}");
-    let initial_config = parse_input(file);
-
-    if let Err(e) = initial_config {
-        println!("Error parsing: {e}");
-        return;
-    }
-
-    let mut state = initial_config.unwrap();
-    let begin_time = Instant::now();
-    let mut moves: usize = 0;
-    loop {
-        let next_move = move_from_state(&state);
-        match next_move {
-            SolverMove::Propagate { variable, clause } => state.add_move(SolverMove::Propagate {
-                variable: variable,
-                clause: clause,
-            }),
-            SolverMove::Decide(var) => {
-                state.add_decision_lv();
-                state.add_move(SolverMove::Decide(var))
+    let mut files: Vec<&String> = Vec::new();
+    let mut json = false;
+    // Number of models to enumerate, if more than one is wanted
+    let mut max_models: Option<usize> = None;
+    let mut config = SolverConfig::new();
+    let mut overrides: Vec<(&str, &str)> = Vec::new();
+    for arg in &args[1..] {
+        if arg == "--json" {
+            json = true;
+        } else if arg == "--all" {
+            max_models = Some(usize::MAX);
+        } else if let Some(limit) = arg.strip_prefix("--max-models=") {
+            match limit.parse::<usize>() {
+                Ok(limit) => max_models = Some(limit),
+                Err(_) => {
+                    println!("Invalid value '{limit}' for max-models");
+                    return;
+                }
+            }
+        } else if let Some(name) = arg.strip_prefix("--preset=") {
+            match SolverConfig::preset(name) {
+                Some(preset) => config = preset,
+                None => {
+                    println!("Unknown preset '{name}'");
+                    return;
+                }
             }
-            SolverMove::Sat() => {
-                let elapsed = begin_time.elapsed();
-                println!("Time elapsed: {:.4?}", elapsed);
-                println!("Moves: {}\nClauses added:{}\n{}", moves, state.clauselist().len() - state.original_clause_count, state.to_string());
-                return;
+        } else if let Some(path) = arg.strip_prefix("--config=") {
+            match SolverConfig::from_file(path) {
+                Ok(loaded) => config = loaded,
+                Err(e) => {
+                    println!("Error reading config: {e}");
+                    return;
+                }
             }
-            SolverMove::Conflict(index) => {
-                if state.resolve_conflict_cdcl(index) {
-                    continue;
-                } else {
-                    let elapsed = begin_time.elapsed();
-                    println!("Time elapsed: {:.4?}", elapsed);
-                    println!("Moves: {}\nUnsat", moves);
+        } else if let Some(option) = arg.strip_prefix("--") {
+            match option.split_once('=') {
+                Some(pair) => overrides.push(pair),
+                None => {
+                    println!("{USAGE}");
                     return;
                 }
             }
-            SolverMove::DecideFromConflict(_, _) => panic!("Next move cannot by DecideFromConflict"),
+        } else {
+            files.push(arg);
         }
-        moves += 1;
     }
-}
-
-fn parse_input(path: &str) -> Result<SolverState, Box<dyn Error>> {
-    let file = File::open(path).expect("Couldn't open file");
-
-    let reader = BufReader::new(file);
 
-    let mut initial_state = SolverState::new();
-    let header_initialized = false;
-    for line in reader.lines() {
-        let line = line?;
-        if line.chars().nth(0).unwrap() == 'c' {
-            continue;
+    // Individual options take precedence over presets and config files regardless of order
+    for (key, value) in overrides {
+        if let Err(e) = config.set(key, value) {
+            println!("{e}");
+            return;
         }
+    }
 
-        if line.chars().nth(0).unwrap() == '%' {
-            break;
-        }
-
-        let splits: Vec<&str> = line.split_whitespace().collect();
-
-        if splits[0] == "p" {
-            if header_initialized {
-                return Err("Header initialized multiple times".into());
-            }
-
-            if splits.len() != 4 {
-                return Err("Bad header format".into());
-            }
-
-            if splits[1] != "cnf" {
-                return Err("Header does not indicate cnf".into());
-            }
-
-            let parsed_variables = splits[2].parse::<usize>();
-            match parsed_variables {
-                Ok(val) => {
-                    initial_state.set_vars(val);
-                    let activity_vec = vec![0.0; val * 2];
-                    initial_state.set_activity(activity_vec);
-                }
-                Err(_) => return Err(format!("Variable count must be a number").into()),
+    let (file, command) = match files[..] {
+        [command, input, formula, reconstruction] if command == "simplify" => {
+            match simplify_file(input, formula, reconstruction, config) {
+                Ok(state) => println!(
+                    "Simplified to {} clauses, {} removed clauses kept for reconstruction",
+                    state.clauses(),
+                    state.reconstruction().len()
+                ),
+                Err(e) => println!("Error simplifying: {e}"),
             }
-
-            let parsed_clauses = splits[3].parse::<usize>();
-            match parsed_clauses {
-                Ok(val) => initial_state.original_clause_count = val,
-                Err(_) => return Err(format!("Clause count must be a number").into()),
+            return;
+        }
+        [command, reconstruction, model] if command == "reconstruct" => {
+            match reconstruct_file(reconstruction, model) {
+                Ok(assignment) => println!("s SATISFIABLE\nv {assignment}0"),
+                Err(e) => println!("Error reconstructing: {e}"),
             }
-            continue;
+            return;
         }
-        let mut clause: Vec<i32> = Vec::new();
-        for num in splits {
-            let parsed_num = num.parse::<i32>();
-
-            match parsed_num {
-                Ok(val) => {
-                    if val != 0 {
-                        clause.push(val)
-                    }
-                }
-                Err(_) => return Err(format!("{num} is not a number").into()),
-            };
+        [command, file] if ["count", "approxmc", "mus", "mcs"].contains(&command.as_str()) => (file, Some(command.as_str())),
+        [file] => (file, None),
+        _ => {
+            println!("{USAGE}");
+            return;
         }
-        initial_state.add_clause(Clause::from_vec(clause));
+    };
+    let mut times = PhaseTimes::default();
+    if !json {
+        println!("Looking for file: {file}");
     }
+    let parse_time = Instant::now();
+    let initial_config = parse_input(file);
+    times.parse = parse_time.elapsed().as_secs_f64();
 
-    Ok(initial_state)
-}
-
-fn move_from_state(state: &SolverState) -> SolverMove {
-    let assignment = Assignment::from_movelist(&state.get_movelist(), state.vars());
-    let mut sat_clauses = 0;
-
-    let mut clause_status = vec![false; state.clauses()]; // True indicates the clause is sat
-    
-    
-    // Loop through clauses and check for possible propagates or conflicts
-    for clause_index in 0..state.clauselist().len() {
-        let clause = &state.clauselist()[clause_index];
-        let clause_result = clause.check_assignment(&assignment);
-
-        match clause_result {
-            Some(status) => match status {
-                AssignmentResult::Propagate(var) => {
-                    return SolverMove::Propagate {
-                        variable: var,
-                        clause: clause_index,
-                    }
-                }
-                AssignmentResult::Conflict() => return SolverMove::Conflict(clause_index),
-                AssignmentResult::Sat() => {
-                    sat_clauses += 1;
-                    clause_status[clause_index] = true
-                }
-            },
-            None => continue,
+    if let Err(e) = initial_config {
+        if json {
+            println!("{}", report::json_error(&e.to_string()));
+        } else {
+            println!("Error parsing: {e}");
         }
+        return;
     }
 
-    if sat_clauses == state.clauses() {
-        return SolverMove::Sat();
+    if json {
+        // Progress reports would corrupt the JSON document
+        config.report_interval = 0;
     }
-
-    // let var = decide_first_unsat(&assignment, &clause_status, state.clauselist());
-    // let var = decide_bohm(&assignment, &clause_status, state.clauselist());
-    let var = decide_activity(&assignment, state, &clause_status, state.clauselist());
-    return SolverMove::Decide(var);
-}
-
-#[allow(dead_code)]
-fn decide_first_unsat(
-    assignment: &Assignment,
-    clause_status: &Vec<bool>,
-    clauses: &Vec<Clause>,
-) -> i32 {
-    for index in 0..clause_status.len() {
-        if clause_status[index] {
-            // Skip over sat clauses
-            continue;
-        }
-        let decide_clause = &clauses[index];
-        for var in &decide_clause.vars {
-            let index: usize = var.abs().try_into().unwrap();
-            if assignment[index].is_none() {
-                return *var;
+    if command.is_some() || max_models.is_some() {
+        config.preserve_models();
+    }
+    let mut state = initial_config.unwrap();
+    state.set_config(config);
+    if !json {
+        println!("Seed: {}", state.seed());
+    }
+    // Subsets are given in clause numbers of the input, which preprocessing would not keep
+    if command == Some("mcs") {
+        let begin_time = Instant::now();
+        // Subsets are only kept for the JSON report, printing needs no more than the count
+        let mut found = Vec::new();
+        let mut count = 0;
+        for mcs in mcs::mcses(&mut state) {
+            count += 1;
+            if json {
+                found.push(mcs);
+            } else {
+                let numbers: Vec<String> = mcs.iter().map(|number| number.to_string()).collect();
+                println!("v {} 0", numbers.join(" "));
             }
         }
-    }
-    panic!("No unsat clauses to decide on");
-}
-
-#[allow(dead_code)]
-fn decide_bohm(assignment: &Assignment, clause_status: &Vec<bool>, clauses: &Vec<Clause>) -> i32 {
-    let alpha = 1;
-    let beta = 2;
-
-    let mut var_to_count_map_map: HashMap<i32, HashMap<usize, usize>> = HashMap::new();
-    // Generate "Vectors" of counts in clauses of size n
-    let mut max_clause_len = 0;
-    let mut last_var = 0;
-    for index in 0..clauses.len() {
-        if clause_status[index] {
-            continue;
+        let elapsed = begin_time.elapsed();
+        times.search = elapsed.as_secs_f64();
+        if json {
+            let report = report::json_mcs_report(&state, &found, &mut times);
+            println!("{}", serde_json::to_string_pretty(&report).unwrap());
+            return;
         }
-        let clause = &clauses[index];
-        let mut adjusted_list: Vec<i32> = Vec::new();
-        for var in &clause.vars {
-            let val = *var;
-            let assignment_index: usize = val.abs().try_into().unwrap();
-            if assignment[assignment_index].is_none() {
-                adjusted_list.push(val);
-                last_var = val;
-            }
+
+        println!("{}", state.stats());
+        println!("Time elapsed: {:.4?}", elapsed);
+        println!("MCSes: {count}");
+        return;
+    }
+    if command == Some("mus") {
+        let begin_time = Instant::now();
+        let mus = mus::extract(&mut state);
+        let elapsed = begin_time.elapsed();
+        times.search = elapsed.as_secs_f64();
+        if json {
+            let report = report::json_mus_report(&state, mus.as_deref(), &mut times);
+            println!("{}", serde_json::to_string_pretty(&report).unwrap());
+            return;
         }
-        let length = adjusted_list.len();
-        max_clause_len = max(max_clause_len, length);
-        for var in adjusted_list {
-            if !var_to_count_map_map.contains_key(&var) {
-                var_to_count_map_map.insert(var, HashMap::new());
-            }
 
-            let len_to_count_map = var_to_count_map_map.get_mut(&var).unwrap();
-            if !len_to_count_map.contains_key(&length) {
-                len_to_count_map.insert(length, 0);
+        println!("{}", state.stats());
+        println!("Time elapsed: {:.4?}", elapsed);
+        match mus {
+            Some(mus) => {
+                let numbers: Vec<String> = mus.iter().map(|number| number.to_string()).collect();
+                println!("s UNSATISFIABLE\nv {} 0", numbers.join(" "));
             }
-            len_to_count_map.insert(length, len_to_count_map.get(&length).unwrap() + 1);
+            None => println!("s SATISFIABLE"),
         }
+        return;
     }
 
-    let empty: HashMap<usize, usize> = HashMap::new();
-    // Find best variable to assign
-    let mut best_var = last_var;
-    for index in 1..=assignment.len() {
-        if assignment[index].is_some() {
-            continue;
+    let preprocess_time = Instant::now();
+    preprocess(&mut state);
+    times.preprocess = preprocess_time.elapsed().as_secs_f64();
+
+    if let Some(command) = command {
+        let approximate = command == "approxmc";
+        let begin_time = Instant::now();
+        let count = if approximate {
+            match approxmc::count(&mut state) {
+                Ok(count) => count,
+                Err(e) => {
+                    if json {
+                        println!("{}", report::json_error(&e));
+                    } else {
+                        println!("{e}");
+                    }
+                    return;
+                }
+            }
+        } else {
+            count::count(&mut state)
+        };
+        let elapsed = begin_time.elapsed();
+        times.search = elapsed.as_secs_f64();
+        if json {
+            let report = report::json_count_report(&state, &count, approximate, &mut times);
+            println!("{}", serde_json::to_string_pretty(&report).unwrap());
+            return;
         }
-        let best_map = var_to_count_map_map.get(&best_var).unwrap_or(&empty);
-        let best_map_inv = var_to_count_map_map.get(&(best_var * -1)).unwrap_or(&empty);
-
-        let cur: i32 = index.try_into().unwrap();
-        let cur_inv: i32 = cur * -1;
-        let cur_map = var_to_count_map_map.get(&cur).unwrap_or(&empty);
-        let cur_map_inv = var_to_count_map_map.get(&cur_inv).unwrap_or(&empty);
 
-        for len in 1..=max_clause_len {
-            let h_cur = match cur_map.get(&len) {
-                Some(count) => *count,
-                None => 0,
-            };
-            let h_cur_inv = match cur_map_inv.get(&len) {
-                Some(count) => *count,
-                None => 0,
-            };
-            let h_best = match best_map.get(&len) {
-                Some(count) => *count,
-                None => 0,
-            };
-            let h_best_inv = match best_map_inv.get(&len) {
-                Some(count) => *count,
-                None => 0,
-            };
+        println!("{}", state.stats());
+        println!("Time elapsed: {:.4?}", elapsed);
+        let kind = match (approximate, state.independent_support().is_some()) {
+            (false, false) => "Models",
+            (false, true) => "Projected models",
+            (true, false) => "Approximate models",
+            (true, true) => "Approximate projected models",
+        };
+        println!("{kind}: {count}");
+        return;
+    }
 
-            let score_cur = alpha * max(h_cur, h_cur_inv) + beta * min(h_cur, h_cur_inv);
-            let score_best = alpha * max(h_best, h_best_inv) + beta * min(h_best, h_best_inv);
-            if score_best == score_cur {
-                continue;
-            } else if score_cur > score_best {
-                best_var = cur;
-                break;
+    if let Some(limit) = max_models {
+        let begin_time = Instant::now();
+        // Models are only kept for the JSON report, printing needs no more than the count
+        let mut found = Vec::new();
+        let mut count = 0;
+        for model in enumerate::models(&mut state).take(limit) {
+            count += 1;
+            if json {
+                found.push(model);
             } else {
-                break;
+                let literals: Vec<String> = model.iter().map(|literal| literal.to_string()).collect();
+                println!("v {} 0", literals.join(" "));
             }
         }
+        let elapsed = begin_time.elapsed();
+        times.search = elapsed.as_secs_f64();
+        let limit_reached = count == limit;
+        if json {
+            let report = report::json_models_report(&state, &found, limit_reached, &mut times);
+            println!("{}", serde_json::to_string_pretty(&report).unwrap());
+            return;
+        }
+
+        println!("{}", state.stats());
+        println!("Time elapsed: {:.4?}", elapsed);
+        if limit_reached {
+            println!("Models: {count} (limit reached)");
+        } else {
+            println!("Models: {count}");
+        }
+        return;
     }
 
-    // Check if best_var or its inverse is best
-    let mut normal_sum = 0;
-    let mut inv_sum = 0;
+    // Soft clauses or an objective make it an optimization problem, solved in the output format
+    // of the MaxSAT and pseudo-Boolean evaluations
+    if !state.soft_clauses().is_empty() || state.objective().is_some() {
+        let vars = state.vars();
+        let begin_time = Instant::now();
+        let optimum = solve_maxsat(&mut state, &mut |cost| {
+            if !json {
+                println!("o {cost}");
+            }
+        });
+        let elapsed = begin_time.elapsed();
+        times.search = elapsed.as_secs_f64();
+        if json {
+            let report = report::json_maxsat_report(&state, optimum.as_ref(), vars, &mut times);
+            println!("{}", serde_json::to_string_pretty(&report).unwrap());
+            return;
+        }
 
-    let best_map = var_to_count_map_map.get(&best_var).unwrap_or(&empty);
-    let best_map_inv = var_to_count_map_map.get(&(best_var * -1)).unwrap_or(&empty);
-    for i in 1..=max_clause_len {
-        normal_sum += match best_map.get(&i) {
-            Some(count) => *count,
-            None => 0,
-        };
-        inv_sum += match best_map_inv.get(&i) {
-            Some(count) => *count,
-            None => 0,
+        println!("{}", state.stats());
+        println!("Time elapsed: {:.4?}", elapsed);
+        let Some(optimum) = optimum else {
+            println!("s UNSATISFIABLE");
+            return;
         };
+        println!("s OPTIMUM FOUND");
+        let model = (1..=vars).map(|var| optimum.model[var] == Some(true));
+        if state.objective().is_some() {
+            let literals: Vec<String> = model
+                .enumerate()
+                .map(|(index, value)| if value { format!("x{}", index + 1) } else { format!("-x{}", index + 1) })
+                .collect();
+            println!("v {}", literals.join(" "));
+        } else {
+            let bits: String = model.map(|value| if value { '1' } else { '0' }).collect();
+            println!("v {bits}");
+        }
+        return;
     }
-    if normal_sum > inv_sum {
-        return best_var;
-    } else {
-        return -1 * best_var;
-    }
-}
-
-#[allow(dead_code)]
-fn decide_activity(assignment: &Assignment, state: &SolverState, clause_status: &Vec<bool>, clauses: &Vec<Clause>) -> i32 {
-    let mut best_activity = 0.0;
-    let mut best_var: i32 = 0;
 
-    // Check activity of all literals
-    for i in 0..state.vars() {
-        let var = <usize as TryInto<i32>>::try_into(i).unwrap() + 1;
-        if assignment[i + 1].is_some() {
-            continue;
+    // Local search only sees clauses, so other constraints leave it to seed the phases of a search
+    if state.config().algorithm == Algorithm::Sls && state.is_clausal() {
+        let begin_time = Instant::now();
+        let result = local_search::search(&mut state);
+        let elapsed = begin_time.elapsed();
+        times.search = elapsed.as_secs_f64();
+        if json {
+            let report = report::json_sls_report(&state, &result, &mut times);
+            println!("{}", serde_json::to_string_pretty(&report).unwrap());
+            return;
         }
-        let var_activty = state.get_activity(var);
-        if var_activty > best_activity {
-            best_activity = var_activty;
-            best_var = var;
+
+        println!("{}", state.stats());
+        println!("Time elapsed: {:.4?}", elapsed);
+        let mut model = result.assignment;
+        state.reconstruction().extend(&mut model);
+        if result.unsatisfied == 0 {
+            println!("Flips: {}\n\nAssignment:\n{}", result.flips, model);
+        } else {
+            println!(
+                "Flips: {}\nUnknown, the best assignment found leaves {} clauses unsatisfied\n\nAssignment:\n{}",
+                result.flips, result.unsatisfied, model
+            );
         }
+        return;
     }
+    let begin_time = Instant::now();
+    let sat = solve(&mut state);
+    let elapsed = begin_time.elapsed();
+    times.search = elapsed.as_secs_f64();
 
-    for i in 0..state.vars() {
-        let var =(<usize as TryInto<i32>>::try_into(i).unwrap() + 1) * -1;
-        if assignment[i + 1].is_some() {
-            continue;
-        }
-        let var_activty = state.get_activity(var);
-        if var_activty > best_activity {
-            best_activity = var_activty;
-            best_var = var
-        }
+    if json {
+        let report = report::json_report(&state, sat, &mut times);
+        println!("{}", serde_json::to_string_pretty(&report).unwrap());
+        return;
     }
-    
-    // If a literal could not be decided on, use bohm
-    // This will only happen as the solver is starting
-    if best_var == 0 {
-        let output =  decide_bohm(assignment, clause_status, clauses);
-        return output;
-        
+
+    println!("{}", state.stats());
+    println!("Time elapsed: {:.4?}", elapsed);
+    let moves = state.stats().moves();
+    if sat {
+        println!("Moves: {}\nClauses added:{}\n{}", moves, state.clauselist().len() - state.original_clause_count, state);
+    } else {
+        println!("Moves: {}\nUnsat", moves);
     }
-    return best_var;
 }
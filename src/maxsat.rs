@@ -0,0 +1,242 @@
+use crate::assignment::Assignment;
+use crate::clause::Clause;
+use crate::config::{Algorithm, MaxSatMethod};
+use crate::pb::Pb;
+use crate::solver::{solve, solve_with_assumptions};
+use crate::solver_state::SolverState;
+
+/// Best assignment found by MaxSAT and the weight of the soft clauses it falsifies, plus the
+/// constant of the objective for OPB input
+#[derive(Clone)]
+pub struct Optimum {
+    pub cost: i64,
+    pub model: Assignment,
+}
+
+// Soft clauses of the formula with the terms of an OPB objective as unit soft clauses, and the
+// constant the objective adds to their cost: a term c * l costs c when l is true, and a
+// negative one is c plus -c when l is false
+fn soft_clauses(state: &SolverState) -> (Vec<(u64, Vec<i32>)>, i64) {
+    let mut soft = state.soft_clauses().to_vec();
+    let mut offset = 0;
+    if let Some(objective) = state.objective() {
+        for (coefficient, literal) in &objective.terms {
+            if *coefficient > 0 {
+                soft.push((coefficient.unsigned_abs(), vec![-literal]));
+            } else if *coefficient < 0 {
+                soft.push((coefficient.unsigned_abs(), vec![*literal]));
+                offset += coefficient;
+            }
+        }
+    }
+    (soft, offset)
+}
+
+// Relaxation literal of each soft clause, true when the clause may be falsified. A unit clause
+// is relaxed by its negated literal, a longer one by a fresh variable added to it as a hard
+// clause.
+fn relax(state: &mut SolverState, soft: &[(u64, Vec<i32>)]) -> Vec<i32> {
+    let long = soft.iter().filter(|(_, clause)| clause.len() != 1).count();
+    let mut fresh = state.add_vars(long);
+    let mut relaxation = Vec::new();
+    for (_, clause) in soft {
+        if let [literal] = clause[..] {
+            relaxation.push(-literal);
+            continue;
+        }
+        let mut relaxed = clause.clone();
+        relaxed.push(fresh as i32);
+        state.add_original_clause(Clause::from_vec(relaxed));
+        relaxation.push(fresh as i32);
+        fresh += 1;
+    }
+    relaxation
+}
+
+// The model of the last search with its open variables set to satisfy soft clauses where they
+// can and false otherwise, which every solver model allows, and its cost
+fn optimum(state: &SolverState, soft: &[(u64, Vec<i32>)], offset: i64) -> Optimum {
+    let mut model = state.model();
+    for (_, clause) in soft {
+        if clause.iter().all(|literal| model.value(*literal) != Some(true)) {
+            if let Some(literal) = clause.iter().find(|literal| model.value(**literal).is_none()) {
+                model.assign(*literal);
+            }
+        }
+    }
+    for var in 1..=model.len() {
+        if model[var].is_none() {
+            model.assign(-(var as i32));
+        }
+    }
+    let falsified: u64 = soft
+        .iter()
+        .filter(|(_, clause)| clause.iter().all(|literal| model.value(*literal) == Some(false)))
+        .map(|(weight, _)| weight)
+        .sum();
+    Optimum { cost: falsified as i64 + offset, model }
+}
+
+/// Minimum cost assignment of the hard clauses and constraints of `state`, where the cost is the
+/// weight of the soft clauses falsified plus the value of the objective, or None if the hard
+/// part has no model. Each model found on the way is passed to `improved` with its cost.
+///
+/// Relaxation variables and the constraints of the search are added to `state`, so the model
+/// covers more variables than the input.
+pub fn solve_maxsat(state: &mut SolverState, improved: &mut dyn FnMut(i64)) -> Option<Optimum> {
+    // Cores need reasons to be traced through, which DPLL does not keep
+    if state.config().maxsat == MaxSatMethod::Linear || state.config().algorithm == Algorithm::Dpll {
+        linear(state, improved)
+    } else {
+        oll(state, improved)
+    }
+}
+
+// SAT-UNSAT search: each model bounds the weight of the relaxation literals below its own
+// cost until no model is left, and the last one is optimal
+fn linear(state: &mut SolverState, improved: &mut dyn FnMut(i64)) -> Option<Optimum> {
+    let (soft, offset) = soft_clauses(state);
+    let relaxation = relax(state, &soft);
+    let mut best: Option<Optimum> = None;
+    while solve(state) {
+        let found = optimum(state, &soft, offset);
+        improved(found.cost);
+        let falsified = found.cost - offset;
+        best = Some(found);
+        if falsified == 0 {
+            break;
+        }
+        let terms = soft.iter().zip(&relaxation).map(|((weight, _), literal)| (*weight as i64, *literal)).collect();
+        state.add_pb(Pb::at_most(terms, falsified - 1));
+    }
+    best
+}
+
+// OLL: solves assuming every relaxation literal with weight left is false. A core of them costs
+// at least its smallest weight, which is taken off each of them, and in exchange the number of
+// them that are true beyond one becomes soft through totalizer-like outputs: o_j for j from 2
+// is forced true once j of the core are true and carries the same weight.
+fn oll(state: &mut SolverState, improved: &mut dyn FnMut(i64)) -> Option<Optimum> {
+    let (soft, offset) = soft_clauses(state);
+    let relaxation = relax(state, &soft);
+    // Weight still charged for each relaxation literal, merged over repeated literals
+    let mut weights: Vec<(i32, u64)> = Vec::new();
+    for ((weight, _), literal) in soft.iter().zip(&relaxation) {
+        match weights.iter_mut().find(|(other, _)| other == literal) {
+            Some((_, total)) => *total += weight,
+            None => weights.push((*literal, *weight)),
+        }
+    }
+
+    loop {
+        let assumptions: Vec<i32> = weights.iter().filter(|(_, weight)| *weight > 0).map(|(literal, _)| -literal).collect();
+        if solve_with_assumptions(state, &assumptions) {
+            let found = optimum(state, &soft, offset);
+            improved(found.cost);
+            return Some(found);
+        }
+        let core: Vec<i32> = state.core().iter().map(|assumption| -assumption).collect();
+        if core.is_empty() {
+            return None;
+        }
+        state.stats_mut().cores += 1;
+
+        let least = weights
+            .iter()
+            .filter(|(literal, _)| core.contains(literal))
+            .map(|(_, weight)| *weight)
+            .min()
+            .unwrap_or(0);
+        for (literal, weight) in weights.iter_mut() {
+            if core.contains(literal) {
+                *weight -= least;
+            }
+        }
+        if core.len() > 1 {
+            let first = state.add_vars(core.len() - 1);
+            for j in 2..=core.len() {
+                let output = (first + j - 2) as i32;
+                // At most j - 1 of the core are true unless the output is
+                let mut terms: Vec<(i64, i32)> = core.iter().map(|literal| (1, *literal)).collect();
+                terms.push((-((core.len() - j + 1) as i64), output));
+                state.add_pb(Pb::at_most(terms, j as i64 - 1));
+                weights.push((output, least));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::solve_maxsat;
+    use crate::config::{Algorithm, MaxSatMethod};
+    use crate::parser::{parse_opb_reader, parse_wcnf_reader};
+    use crate::solver_state::SolverState;
+    use crate::testutil::brute_force_models;
+
+    // Cheapest model of the hard clauses by trying every one
+    fn brute_force(state: &SolverState) -> Option<i64> {
+        let falsified = |model: &Vec<i32>| {
+            let soft = state.soft_clauses().iter().filter(|(_, clause)| !clause.iter().any(|literal| model.contains(literal)));
+            soft.map(|(weight, _)| *weight as i64).sum()
+        };
+        brute_force_models(state).iter().map(falsified).min()
+    }
+
+    fn optimize(input: &str, method: MaxSatMethod, algorithm: Algorithm) -> (Option<i64>, Vec<i64>) {
+        let mut state = parse_wcnf_reader(input.as_bytes()).unwrap();
+        let mut config = state.config().clone();
+        config.maxsat = method;
+        config.algorithm = algorithm;
+        config.report_interval = 0;
+        state.set_config(config);
+        let soft = state.soft_clauses().to_vec();
+        let mut costs = Vec::new();
+        let optimum = solve_maxsat(&mut state, &mut |cost| costs.push(cost));
+        if let Some(optimum) = &optimum {
+            let falsified: u64 = soft
+                .iter()
+                .filter(|(_, clause)| clause.iter().all(|literal| optimum.model.value(*literal) == Some(false)))
+                .map(|(weight, _)| weight)
+                .sum();
+            assert_eq!(falsified as i64, optimum.cost);
+        }
+        (optimum.map(|optimum| optimum.cost), costs)
+    }
+
+    #[test]
+    fn optimum_matches_brute_force() {
+        let inputs = [
+            "p wcnf 4 8 100\n100 1 2 0\n100 -1 -2 0\n100 3 4 0\n3 -1 0\n2 -2 0\n4 -3 0\n4 -4 0\n1 1 3 0\n",
+            "h 1 2 3 0\nh -1 -2 0\nh -2 -3 0\n5 -1 0\n5 -2 0\n5 -3 0\n2 1 2 0\n7 -1 -3 0\n",
+            "p wcnf 3 6\n1 1 0\n1 2 0\n1 3 0\n2 -1 -2 0\n2 -2 -3 0\n2 -1 -3 0\n",
+        ];
+        for input in inputs {
+            let expected = brute_force(&parse_wcnf_reader(input.as_bytes()).unwrap());
+            assert!(expected.is_some());
+            for (method, algorithm) in [
+                (MaxSatMethod::Oll, Algorithm::Cdcl),
+                (MaxSatMethod::Linear, Algorithm::Cdcl),
+                (MaxSatMethod::Oll, Algorithm::Dpll),
+            ] {
+                let (cost, costs) = optimize(input, method, algorithm);
+                assert_eq!(cost, expected, "{input}");
+                assert!(costs.windows(2).all(|pair| pair[0] > pair[1]));
+            }
+        }
+
+        let unsat = "h 1 0\nh -1 0\n3 2 0\n";
+        assert_eq!(optimize(unsat, MaxSatMethod::Oll, Algorithm::Cdcl).0, None);
+        assert_eq!(optimize(unsat, MaxSatMethod::Linear, Algorithm::Cdcl).0, None);
+    }
+
+    #[test]
+    fn objective_is_minimized() {
+        // Two of three must hold, and -2 ~x2 is -2 + 2 x2, so the cheapest pair is x1 and x2
+        let input = "* #variable= 3 #constraint= 1\nmin: +3 x1 -2 ~x2 +4 x3 ;\n+1 x1 +1 x2 +1 x3 >= 2 ;\n";
+        let mut state = parse_opb_reader(input.as_bytes()).unwrap();
+        let optimum = solve_maxsat(&mut state, &mut |_| ()).unwrap();
+        assert_eq!(optimum.cost, 3);
+        assert_eq!(state.objective().unwrap().value(&optimum.model), 3);
+    }
+}
@@ -0,0 +1,119 @@
+use crate::mus::Selectors;
+use crate::solver_state::SolverState;
+
+/// Iterator over the minimal correction subsets of the original clauses of a formula, or of its
+/// groups for a GCNF formula: the sets whose removal leaves the rest with a model while keeping
+/// any one of them back does not. Each is yielded as sorted clause or group numbers as soon as
+/// it is found. A satisfiable formula has only the empty one and a formula whose group 0 has
+/// no model has none.
+///
+/// Each subset is found by CLD: a model splits the groups into those it satisfies and the rest,
+/// and a search keeping the satisfied groups on while requiring one of the rest to hold moves
+/// more of them over until none can be. A clause requiring one of its groups to be on blocks
+/// the subset, and its supersets, from later searches.
+pub struct Mcses<'a> {
+    state: &'a mut SolverState,
+    selectors: Selectors,
+    exhausted: bool,
+}
+
+/// Enumerates the minimal correction subsets of `state`, which is left as it is apart from the
+/// statistics of the searches
+pub fn mcses(state: &mut SolverState) -> Mcses<'_> {
+    let selectors = Selectors::new(state);
+    Mcses { state, selectors, exhausted: false }
+}
+
+impl Iterator for Mcses<'_> {
+    type Item = Vec<usize>;
+
+    fn next(&mut self) -> Option<Vec<usize>> {
+        if self.exhausted || !self.selectors.solve(&[]) {
+            self.exhausted = true;
+            self.selectors.merge_stats(self.state);
+            return None;
+        }
+
+        let groups = self.selectors.groups();
+        let mut falsified = self.selectors.falsified(&self.selectors.model(), &groups);
+        falsified.retain(|group| *group != 0);
+        let mut satisfied: Vec<usize> = groups.into_iter().filter(|group| !falsified.contains(group)).collect();
+        while !falsified.is_empty() && self.selectors.solve_requiring(&satisfied, &falsified) {
+            let still_falsified = self.selectors.falsified(&self.selectors.model(), &falsified);
+            satisfied.extend(falsified.iter().filter(|group| !still_falsified.contains(group)));
+            falsified.retain(|group| still_falsified.contains(group));
+        }
+
+        if falsified.is_empty() {
+            // Every group holds together, nothing else needs correcting
+            self.exhausted = true;
+        } else {
+            self.selectors.require_any(&falsified);
+        }
+        self.selectors.merge_stats(self.state);
+        Some(falsified)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::mcses;
+    use crate::parser::{parse_gcnf_reader, parse_reader};
+    use crate::testutil::{dimacs, random_clauses, satisfiable};
+
+    // Clause numbers, counting from 1, of every subset whose removal leaves a satisfiable rest
+    // while putting any one of them back does not
+    fn brute_force(clauses: &[Vec<i32>], vars: usize) -> Vec<Vec<usize>> {
+        let satisfiable = |kept: &dyn Fn(usize) -> bool| {
+            let rest: Vec<&Vec<i32>> = clauses.iter().enumerate().filter(|(index, _)| kept(*index)).map(|(_, clause)| clause).collect();
+            satisfiable(&rest, vars)
+        };
+        let mut found = Vec::new();
+        for removed in 0..1u32 << clauses.len() {
+            if !satisfiable(&|index| removed >> index & 1 == 0) {
+                continue;
+            }
+            let minimal = (0..clauses.len())
+                .filter(|index| removed >> index & 1 == 1)
+                .all(|index| !satisfiable(&|other| removed >> other & 1 == 0 || other == index));
+            if minimal {
+                found.push((0..clauses.len()).filter(|index| removed >> index & 1 == 1).map(|index| index + 1).collect());
+            }
+        }
+        found
+    }
+
+    #[test]
+    fn all_minimal_correction_subsets_are_found() {
+        let mut rng = StdRng::seed_from_u64(11);
+        for _ in 0..15 {
+            let vars = 4;
+            let clauses = random_clauses(&mut rng, vars, 10, 2);
+            let input = dimacs(vars, &clauses);
+            let mut state = parse_reader(input.as_bytes()).unwrap();
+            let mut found: Vec<Vec<usize>> = mcses(&mut state).collect();
+            found.sort();
+            let mut expected = brute_force(&clauses, vars as usize);
+            expected.sort();
+            assert_eq!(found, expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn groups_and_kept_clauses() {
+        // Group 1 and group 2 contradict each other through the kept clause
+        let input = "p gcnf 2 4 2\n{0} -1 -2 0\n{1} 1 0\n{2} 2 0\n{2} -1 2 0\n";
+        let mut state = parse_gcnf_reader(input.as_bytes()).unwrap();
+        let mut found: Vec<Vec<usize>> = mcses(&mut state).collect();
+        found.sort();
+        assert_eq!(found, vec![vec![1], vec![2]]);
+
+        let input = "p gcnf 1 2 1\n{0} 1 0\n{0} -1 0\n";
+        assert_eq!(mcses(&mut parse_gcnf_reader(input.as_bytes()).unwrap()).count(), 0);
+        let input = "p cnf 2 2\n1 0\n2 0\n";
+        assert_eq!(mcses(&mut parse_reader(input.as_bytes()).unwrap()).collect::<Vec<_>>(), vec![Vec::<usize>::new()]);
+    }
+}
@@ -0,0 +1,291 @@
+use std::collections::BTreeMap;
+
+use crate::assignment::Assignment;
+use crate::clause::Clause;
+use crate::solver::solve_with_assumptions;
+use crate::solver_state::SolverState;
+use crate::statistics::Statistics;
+
+/// The original clauses of a formula in groups that can be switched on and off between searches,
+/// each through a selector variable added to its clauses. The groups are those of a GCNF
+/// formula, where group 0 is always on, or one per clause numbered from 1 in input order.
+pub struct Selectors {
+    state: SolverState,
+    // Original clauses with their groups
+    clauses: Vec<(usize, Vec<i32>)>,
+    // Selector variable of each group that can be switched off
+    selectors: BTreeMap<usize, i32>,
+    // Variable switching on the clause of the last `solve_requiring`, switched off for good
+    // before the next search
+    activation: Option<i32>,
+}
+
+impl Selectors {
+    /// Builds the selectable copy of the original clauses and constraints of `state`, which is
+    /// left as it is
+    pub fn new(state: &SolverState) -> Selectors {
+        let original = &state.clauselist()[..state.original_clause_count];
+        let groups: Vec<usize> = match state.groups() {
+            Some(groups) => groups.to_vec(),
+            None => (1..=original.len()).collect(),
+        };
+        let clauses: Vec<(usize, Vec<i32>)> = groups.into_iter().zip(original).map(|(group, clause)| (group, clause.vars.clone())).collect();
+
+        let mut next_var = state.vars();
+        let mut selectors = BTreeMap::new();
+        for (group, _) in &clauses {
+            if *group != 0 {
+                selectors.entry(*group).or_insert_with(|| {
+                    next_var += 1;
+                    next_var as i32
+                });
+            }
+        }
+
+        let mut selectable = SolverState::new();
+        selectable.set_vars(next_var);
+        for (group, clause) in &clauses {
+            let mut vars = clause.clone();
+            if let Some(selector) = selectors.get(group) {
+                vars.push(-selector);
+            }
+            selectable.add_clause(Clause::from_vec(vars));
+        }
+        selectable.original_clause_count = selectable.clauses();
+        for xor in state.xors() {
+            selectable.add_xor(xor.clone());
+        }
+        for pb in state.pbs() {
+            selectable.add_pb(pb.clone());
+        }
+        let mut config = state.config().clone();
+        config.report_interval = 0;
+        selectable.set_config(config);
+        Selectors { state: selectable, clauses, selectors, activation: None }
+    }
+
+    /// Groups that can be switched off, in increasing order
+    pub fn groups(&self) -> Vec<usize> {
+        self.selectors.keys().copied().collect()
+    }
+
+    /// Whether the always kept clauses have a model together with the groups in `active`
+    pub fn solve(&mut self, active: &[usize]) -> bool {
+        self.retract();
+        let assumptions: Vec<i32> = active.iter().map(|group| self.selectors[group]).collect();
+        solve_with_assumptions(&mut self.state, &assumptions)
+    }
+
+    /// Like `solve`, additionally requiring one of the groups in `any_of` to be on for this
+    /// search only
+    pub fn solve_requiring(&mut self, active: &[usize], any_of: &[usize]) -> bool {
+        self.retract();
+        let activation = self.state.add_vars(1) as i32;
+        let mut clause: Vec<i32> = any_of.iter().map(|group| self.selectors[group]).collect();
+        clause.push(-activation);
+        self.state.add_original_clause(Clause::from_vec(clause));
+        self.activation = Some(activation);
+
+        let mut assumptions: Vec<i32> = active.iter().map(|group| self.selectors[group]).collect();
+        assumptions.push(activation);
+        solve_with_assumptions(&mut self.state, &assumptions)
+    }
+
+    // Switches off the clause of the last `solve_requiring`, which undoes the trail, so it waits
+    // until the model of that search is no longer needed
+    fn retract(&mut self) {
+        if let Some(activation) = self.activation.take() {
+            self.state.add_original_clause(Clause::from_vec(vec![-activation]));
+        }
+    }
+
+    /// Requires one of `groups` to be on in every later search
+    pub fn require_any(&mut self, groups: &[usize]) {
+        self.retract();
+        let clause = groups.iter().map(|group| self.selectors[group]).collect();
+        self.state.add_original_clause(Clause::from_vec(clause));
+    }
+
+    /// Groups of the last search without a model that are enough to rule one out. Empty when
+    /// the always kept clauses have none by themselves.
+    pub fn core(&self) -> Vec<usize> {
+        let core = self.state.core();
+        self.selectors
+            .iter()
+            .filter(|(_, selector)| core.contains(selector))
+            .map(|(group, _)| *group)
+            .collect()
+    }
+
+    /// Model of the last search, with the variables it leaves open false as any value will do
+    pub fn model(&self) -> Assignment {
+        let mut model = self.state.model();
+        for var in 1..=model.len() {
+            if model[var].is_none() {
+                model.assign(-(var as i32));
+            }
+        }
+        model
+    }
+
+    /// Groups among `groups` with a clause `model` falsifies, with 0 standing for the always
+    /// kept clauses
+    pub fn falsified(&self, model: &Assignment, groups: &[usize]) -> Vec<usize> {
+        let mut falsified: Vec<usize> = self
+            .clauses
+            .iter()
+            .filter(|(group, clause)| {
+                (*group == 0 || groups.contains(group)) && clause.iter().all(|literal| model.value(*literal) == Some(false))
+            })
+            .map(|(group, _)| *group)
+            .collect();
+        falsified.sort();
+        falsified.dedup();
+        falsified
+    }
+
+    /// Clauses of `group`
+    pub fn clauses(&self, group: usize) -> impl Iterator<Item = &Vec<i32>> {
+        self.clauses.iter().filter(move |(other, _)| *other == group).map(|(_, clause)| clause)
+    }
+
+    /// Moves the search statistics of the selectable copy over to those of `state`
+    pub fn merge_stats(&mut self, state: &mut SolverState) {
+        let own = std::mem::take(self.state.stats_mut());
+        let stats = state.stats_mut();
+        stats.decisions += own.decisions;
+        stats.propagations += own.propagations;
+        stats.conflicts += own.conflicts;
+        stats.xor_explanations += own.xor_explanations;
+        stats.pb_explanations += own.pb_explanations;
+        stats.rotations += own.rotations;
+    }
+
+    fn is_clausal(&self) -> bool {
+        self.state.is_clausal()
+    }
+
+    fn stats_mut(&mut self) -> &mut Statistics {
+        self.state.stats_mut()
+    }
+}
+
+/// Minimal unsatisfiable subset of the original clauses of `state`, or of its groups for a GCNF
+/// formula, as the sorted clause numbers counting from 1 or group numbers. None if the formula
+/// has a model; empty if the clauses of group 0 have none by themselves.
+///
+/// Deletion-based: each candidate left is dropped in turn, and stays dropped if the rest still
+/// has no model, along with every other one outside the core that search found. Otherwise it
+/// is necessary, and model rotation flips the literals of its falsified clauses in the model
+/// found to look for other candidates that are the only ones a neighbouring model falsifies,
+/// which are necessary too without a search of their own.
+pub fn extract(state: &mut SolverState) -> Option<Vec<usize>> {
+    let mut selectors = Selectors::new(state);
+    let all = selectors.groups();
+    let found = if selectors.solve(&all) {
+        None
+    } else {
+        let mut necessary: Vec<usize> = Vec::new();
+        let mut unknown = selectors.core();
+        while let Some(group) = unknown.pop() {
+            let active: Vec<usize> = necessary.iter().chain(&unknown).copied().collect();
+            if selectors.solve(&active) {
+                let model = selectors.model();
+                necessary.push(group);
+                rotate(&mut selectors, model, group, &mut necessary, &mut unknown);
+            } else {
+                let core = selectors.core();
+                unknown.retain(|group| core.contains(group));
+            }
+        }
+        necessary.sort();
+        Some(necessary)
+    };
+    selectors.merge_stats(state);
+    found
+}
+
+// Recursive model rotation from `model`, which satisfies every candidate but `group`. Rotation
+// checks models against clauses only, so other constraints turn it off.
+fn rotate(selectors: &mut Selectors, model: Assignment, group: usize, necessary: &mut Vec<usize>, unknown: &mut Vec<usize>) {
+    if !selectors.is_clausal() {
+        return;
+    }
+    let mut pending = vec![(group, model)];
+    while let Some((group, model)) = pending.pop() {
+        let literals: Vec<i32> = selectors
+            .clauses(group)
+            .filter(|clause| clause.iter().all(|literal| model.value(*literal) == Some(false)))
+            .flatten()
+            .copied()
+            .collect();
+        for literal in literals {
+            let mut rotated = model.clone();
+            rotated.assign(literal);
+            let candidates: Vec<usize> = necessary.iter().chain(unknown.iter()).copied().collect();
+            if let [other] = selectors.falsified(&rotated, &candidates)[..] {
+                if let Some(position) = unknown.iter().position(|group| *group == other) {
+                    unknown.remove(position);
+                    necessary.push(other);
+                    selectors.stats_mut().rotations += 1;
+                    pending.push((other, rotated));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::extract;
+    use crate::parser::{parse_gcnf_reader, parse_reader};
+    use crate::testutil::{dimacs, random_clauses, satisfiable};
+
+    #[test]
+    fn subsets_are_minimal_and_unsatisfiable() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let mut rotations = 0;
+        let mut checked = 0;
+        while checked < 20 {
+            let vars = 5;
+            let clauses = random_clauses(&mut rng, vars, 24, 3);
+            let all: Vec<&Vec<i32>> = clauses.iter().collect();
+            let mut state = parse_reader(dimacs(vars, &clauses).as_bytes()).unwrap();
+            let mus = extract(&mut state);
+            rotations += state.stats().rotations;
+            if satisfiable(&all, vars as usize) {
+                assert!(mus.is_none());
+                continue;
+            }
+            checked += 1;
+
+            let mus = mus.unwrap();
+            let subset: Vec<&Vec<i32>> = mus.iter().map(|index| &clauses[index - 1]).collect();
+            assert!(!satisfiable(&subset, vars as usize));
+            for skipped in 0..subset.len() {
+                let rest: Vec<&Vec<i32>> = subset.iter().enumerate().filter(|(index, _)| *index != skipped).map(|(_, clause)| *clause).collect();
+                assert!(satisfiable(&rest, vars as usize), "{mus:?} is not minimal");
+            }
+        }
+        assert!(rotations > 0);
+    }
+
+    #[test]
+    fn groups_are_kept_or_dropped_together() {
+        // With the kept clause 1, group 2 forces 2, which group 3 rules out, and group 1 is not
+        // needed
+        let input = "p gcnf 3 5 3\n{0} 1 0\n{1} 2 3 0\n{2} -1 2 0\n{2} -1 -3 0\n{3} -2 0\n";
+        let mut state = parse_gcnf_reader(input.as_bytes()).unwrap();
+        assert_eq!(state.groups(), Some(&[0, 1, 2, 2, 3][..]));
+        assert_eq!(extract(&mut state), Some(vec![2, 3]));
+
+        let input = "p gcnf 1 3 2\n{0} 1 0\n{0} -1 0\n{1} 1 0\n";
+        assert_eq!(extract(&mut parse_gcnf_reader(input.as_bytes()).unwrap()), Some(vec![]));
+        let input = "p gcnf 2 2 2\n{1} 1 0\n{2} 2 0\n";
+        assert_eq!(extract(&mut parse_gcnf_reader(input.as_bytes()).unwrap()), None);
+        assert!(parse_gcnf_reader("p gcnf 1 1 1\n1 0\n".as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,420 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use crate::clause::Clause;
+use crate::pb::{Objective, Pb};
+use crate::solver_state::SolverState;
+use crate::xor::Xor;
+
+/// Reads a formula from `path`, in the OPB format if the extension is `.opb`, as weighted
+/// MaxSAT if it is `.wcnf`, as group CNF if it is `.gcnf` and in DIMACS otherwise
+pub fn parse_input(path: &str) -> Result<SolverState, Box<dyn Error>> {
+    let file = File::open(path)?;
+
+    match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+        Some("opb") => parse_opb_reader(BufReader::new(file)),
+        Some("wcnf") => parse_wcnf_reader(BufReader::new(file)),
+        Some("gcnf") => parse_gcnf_reader(BufReader::new(file)),
+        _ => parse_reader(BufReader::new(file)),
+    }
+}
+
+// Rejects variables outside the range the header declares, which would otherwise only fail
+// once the solver indexes by them
+fn check_var(var: usize, vars: usize) -> Result<(), Box<dyn Error>> {
+    if var == 0 || var > vars {
+        return Err(format!("Variable {var} is beyond the {vars} variables of the header").into());
+    }
+    Ok(())
+}
+
+pub fn parse_reader<R: BufRead>(reader: R) -> Result<SolverState, Box<dyn Error>> {
+    let mut initial_state = SolverState::new();
+    let header_initialized = false;
+    for line in reader.lines() {
+        let line = line?;
+        if let Some(support) = line.strip_prefix("c ind ") {
+            let mut vars = Vec::new();
+            for num in support.split_whitespace() {
+                match num.parse::<usize>() {
+                    Ok(0) => break,
+                    Ok(var) => vars.push(var),
+                    Err(_) => return Err(format!("{num} is not a variable").into()),
+                }
+            }
+            initial_state.add_independent_support(&vars);
+            continue;
+        }
+        if let Some(xor) = line.strip_prefix('x') {
+            // The XOR of the literals is true, each negation flips the parity
+            let mut vars = Vec::new();
+            let mut parity = true;
+            for num in xor.split_whitespace() {
+                match num.parse::<i32>() {
+                    Ok(0) => break,
+                    Ok(literal) => {
+                        check_var(literal.unsigned_abs() as usize, initial_state.vars())?;
+                        vars.push(literal.unsigned_abs() as usize);
+                        parity ^= literal < 0;
+                    }
+                    Err(_) => return Err(format!("{num} is not a number").into()),
+                }
+            }
+            initial_state.add_xor(Xor::new(vars, parity));
+            continue;
+        }
+        if line.starts_with('c') || line.trim().is_empty() {
+            continue;
+        }
+
+        if line.starts_with('%') {
+            break;
+        }
+
+        let splits: Vec<&str> = line.split_whitespace().collect();
+
+        if splits[0] == "p" {
+            if header_initialized {
+                return Err("Header initialized multiple times".into());
+            }
+
+            if splits.len() != 4 {
+                return Err("Bad header format".into());
+            }
+
+            // MiniCard's `cnf+` allows cardinality constraints
+            if splits[1] != "cnf" && splits[1] != "cnf+" {
+                return Err("Header does not indicate cnf".into());
+            }
+
+            let parsed_variables = splits[2].parse::<usize>();
+            match parsed_variables {
+                Ok(val) => initial_state.set_vars(val),
+                Err(_) => return Err("Variable count must be a number".into()),
+            }
+
+            let parsed_clauses = splits[3].parse::<usize>();
+            match parsed_clauses {
+                Ok(val) => initial_state.original_clause_count = val,
+                Err(_) => return Err("Clause count must be a number".into()),
+            }
+            continue;
+        }
+        if let Some(position) = splits.iter().position(|split| *split == "<=" || *split == ">=") {
+            // Cardinality constraint in MiniCard's format, `1 -2 3 <= 2`
+            let mut literals = Vec::new();
+            for num in &splits[..position] {
+                match num.parse::<i32>() {
+                    Ok(literal) => {
+                        check_var(literal.unsigned_abs() as usize, initial_state.vars())?;
+                        literals.push(literal);
+                    }
+                    Err(_) => return Err(format!("{num} is not a number").into()),
+                }
+            }
+            let k = match splits.get(position + 1).map(|num| num.parse::<usize>()) {
+                Some(Ok(k)) => k,
+                _ => return Err(format!("Bad cardinality constraint '{line}'").into()),
+            };
+            initial_state.add_pb(match splits[position] {
+                "<=" => Pb::cardinality_at_most(&literals, k),
+                _ => Pb::cardinality_at_least(&literals, k),
+            });
+            continue;
+        }
+        let mut clause: Vec<i32> = Vec::new();
+        for num in splits {
+            let parsed_num = num.parse::<i32>();
+
+            match parsed_num {
+                Ok(val) => {
+                    if val != 0 {
+                        check_var(val.unsigned_abs() as usize, initial_state.vars())?;
+                        clause.push(val)
+                    }
+                }
+                Err(_) => return Err(format!("{num} is not a number").into()),
+            };
+        }
+        initial_state.add_clause(Clause::from_vec(clause));
+    }
+
+    if !initial_state.is_clausal() {
+        // The header counts the other constraints among the clauses
+        initial_state.original_clause_count = initial_state.clauses();
+    }
+    // The independent support may come before the header
+    for var in initial_state.independent_support().unwrap_or_default() {
+        check_var(*var, initial_state.vars())?;
+    }
+    Ok(initial_state)
+}
+
+/// Reads weighted partial MaxSAT in either WCNF format: the old one with a `p wcnf <vars>
+/// <clauses> <top>` header, where clauses weighing at least top are hard, and the 2022 one
+/// without a header, where hard clauses start with `h`. Soft clauses start with their weight.
+pub fn parse_wcnf_reader<R: BufRead>(reader: R) -> Result<SolverState, Box<dyn Error>> {
+    let mut initial_state = SolverState::new();
+    let mut vars = 0;
+    // Weight from which clauses are hard in the old format, if the header gives one
+    let mut top: Option<u64> = None;
+    for line in reader.lines() {
+        let line = line?;
+        if line.starts_with('c') || line.trim().is_empty() {
+            continue;
+        }
+
+        let splits: Vec<&str> = line.split_whitespace().collect();
+        if splits[0] == "p" {
+            if splits.len() < 4 || splits.len() > 5 || splits[1] != "wcnf" {
+                return Err("Bad header format".into());
+            }
+            match splits[2].parse::<usize>() {
+                Ok(val) => vars = val,
+                Err(_) => return Err("Variable count must be a number".into()),
+            }
+            if let Some(weight) = splits.get(4) {
+                match weight.parse::<u64>() {
+                    Ok(val) => top = Some(val),
+                    Err(_) => return Err("Top weight must be a number".into()),
+                }
+            }
+            continue;
+        }
+
+        let mut clause: Vec<i32> = Vec::new();
+        for num in &splits[1..] {
+            match num.parse::<i32>() {
+                Ok(0) => break,
+                Ok(literal) => {
+                    vars = vars.max(literal.unsigned_abs() as usize);
+                    clause.push(literal);
+                }
+                Err(_) => return Err(format!("{num} is not a number").into()),
+            }
+        }
+        if splits[0] == "h" {
+            initial_state.add_clause(Clause::from_vec(clause));
+            continue;
+        }
+        match splits[0].parse::<u64>() {
+            Ok(weight) if top.is_some_and(|top| weight >= top) => initial_state.add_clause(Clause::from_vec(clause)),
+            Ok(0) => continue,
+            Ok(weight) => initial_state.add_soft_clause(weight, clause),
+            Err(_) => return Err(format!("{} is not a weight", splits[0]).into()),
+        }
+    }
+    initial_state.set_vars(vars);
+    initial_state.original_clause_count = initial_state.clauses();
+
+    Ok(initial_state)
+}
+
+/// Reads group CNF as used for group MUS extraction: a `p gcnf <vars> <clauses> <groups>`
+/// header and clauses prefixed by their group as in `{2} 1 -3 0`, where group 0 is always kept
+pub fn parse_gcnf_reader<R: BufRead>(reader: R) -> Result<SolverState, Box<dyn Error>> {
+    let mut initial_state = SolverState::new();
+    let mut groups = Vec::new();
+    let mut vars = 0;
+    for line in reader.lines() {
+        let line = line?;
+        if line.starts_with('c') || line.trim().is_empty() {
+            continue;
+        }
+
+        let splits: Vec<&str> = line.split_whitespace().collect();
+        if splits[0] == "p" {
+            if splits.len() != 5 || splits[1] != "gcnf" {
+                return Err("Bad header format".into());
+            }
+            match splits[2].parse::<usize>() {
+                Ok(val) => vars = vars.max(val),
+                Err(_) => return Err("Variable count must be a number".into()),
+            }
+            continue;
+        }
+
+        let group = splits[0].strip_prefix('{').and_then(|group| group.strip_suffix('}'));
+        match group.map(|group| group.parse::<usize>()) {
+            Some(Ok(group)) => groups.push(group),
+            _ => return Err(format!("{} is not a group", splits[0]).into()),
+        }
+        let mut clause: Vec<i32> = Vec::new();
+        for num in &splits[1..] {
+            match num.parse::<i32>() {
+                Ok(0) => break,
+                Ok(literal) => {
+                    vars = vars.max(literal.unsigned_abs() as usize);
+                    clause.push(literal);
+                }
+                Err(_) => return Err(format!("{num} is not a number").into()),
+            }
+        }
+        initial_state.add_clause(Clause::from_vec(clause));
+    }
+    initial_state.set_vars(vars);
+    initial_state.original_clause_count = initial_state.clauses();
+    initial_state.set_groups(groups);
+
+    Ok(initial_state)
+}
+
+// Literal of an OPB variable name, `x3` or `~x3`
+fn opb_literal(name: &str) -> Result<i32, Box<dyn Error>> {
+    let (negated, name) = match name.strip_prefix('~') {
+        Some(name) => (true, name),
+        None => (false, name),
+    };
+    match name.strip_prefix('x').map(|num| num.parse::<i32>()) {
+        Some(Ok(var)) if var > 0 => Ok(if negated { -var } else { var }),
+        _ => Err(format!("{name} is not a variable").into()),
+    }
+}
+
+// Terms of a linear OPB expression, alternating coefficients and literals
+fn opb_terms(tokens: &[&str]) -> Result<Vec<(i64, i32)>, Box<dyn Error>> {
+    if !tokens.len().is_multiple_of(2) {
+        return Err(format!("Terms must pair coefficients with literals: '{}'", tokens.join(" ")).into());
+    }
+    let mut terms = Vec::new();
+    for pair in tokens.chunks(2) {
+        let coefficient = match pair[0].parse::<i64>() {
+            Ok(coefficient) => coefficient,
+            Err(_) => return Err(format!("{} is not a coefficient", pair[0]).into()),
+        };
+        terms.push((coefficient, opb_literal(pair[1])?));
+    }
+    Ok(terms)
+}
+
+/// Reads the linear OPB format of the pseudo-Boolean competition: `*` comment lines, with the
+/// variable count taken from the `#variable=` header comment, an optional `min:` objective and
+/// constraints with `>=`, `<=` or `=`, each ended by `;`. Equalities become two constraints.
+pub fn parse_opb_reader<R: BufRead>(reader: R) -> Result<SolverState, Box<dyn Error>> {
+    let mut initial_state = SolverState::new();
+    let mut vars = 0;
+    let mut text = String::new();
+    for line in reader.lines() {
+        let line = line?;
+        if let Some(comment) = line.strip_prefix('*') {
+            let mut tokens = comment.split_whitespace();
+            while let Some(token) = tokens.next() {
+                if token == "#variable=" {
+                    match tokens.next().map(|num| num.parse::<usize>()) {
+                        Some(Ok(count)) => vars = count,
+                        _ => return Err("Variable count must be a number".into()),
+                    }
+                }
+            }
+            continue;
+        }
+        text += &line;
+        text += " ";
+    }
+
+    let mut statements: Vec<&str> = text.split(';').collect();
+    if !statements.pop().unwrap_or_default().trim().is_empty() {
+        return Err("Constraint not ended by ';'".into());
+    }
+    for statement in statements {
+        let tokens: Vec<&str> = statement.split_whitespace().collect();
+        if let Some(("min:", objective)) = tokens.split_first().map(|(first, rest)| (*first, rest)) {
+            initial_state.set_objective(Objective { terms: opb_terms(objective)? });
+            continue;
+        }
+        let relation = tokens
+            .iter()
+            .position(|token| matches!(*token, ">=" | "<=" | "="))
+            .filter(|position| position + 2 == tokens.len());
+        let Some(position) = relation else {
+            return Err(format!("Bad constraint '{}'", statement.trim()).into());
+        };
+        let bound = match tokens[position + 1].parse::<i64>() {
+            Ok(bound) => bound,
+            Err(_) => return Err(format!("{} is not a bound", tokens[position + 1]).into()),
+        };
+        let terms = opb_terms(&tokens[..position])?;
+        for (_, literal) in &terms {
+            vars = vars.max(literal.unsigned_abs() as usize);
+        }
+        match tokens[position] {
+            ">=" => initial_state.add_pb(Pb::at_least(terms, bound)),
+            "<=" => initial_state.add_pb(Pb::at_most(terms, bound)),
+            _ => {
+                initial_state.add_pb(Pb::at_least(terms.clone(), bound));
+                initial_state.add_pb(Pb::at_most(terms, bound));
+            }
+        }
+    }
+    if let Some(objective) = initial_state.objective() {
+        for (_, literal) in &objective.terms {
+            vars = vars.max(literal.unsigned_abs() as usize);
+        }
+    }
+    initial_state.set_vars(vars);
+
+    Ok(initial_state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_opb_reader, parse_reader, parse_wcnf_reader};
+    use crate::enumerate::models;
+    use crate::pb::{Objective, Pb};
+
+    #[test]
+    fn opb_constraints_and_objective() {
+        let input = "* #variable= 4 #constraint= 3\n\
+                     min: +2 x1 -1 ~x3 ;\n\
+                     +1 x1 +1 x2 +1 x3 >= 2 ;\n\
+                     +2 x1 -3 x2\n+1 ~x4 <= 0 ;\n\
+                     +1 x3 +1 x4 = 1 ;\n";
+        let mut state = parse_opb_reader(input.as_bytes()).unwrap();
+        assert_eq!(state.vars(), 4);
+        assert_eq!(state.objective(), Some(&Objective { terms: vec![(2, 1), (-1, -3)] }));
+        assert_eq!(state.pbs().len(), 4);
+        assert_eq!(state.pbs()[0], Pb::cardinality_at_least(&[1, 2, 3], 2));
+
+        let mut found: Vec<Vec<i32>> = models(&mut state).collect();
+        found.sort();
+        assert_eq!(found, vec![vec![-1, 2, 3, -4], vec![1, 2, -3, 4], vec![1, 2, 3, -4]]);
+
+        assert!(parse_opb_reader("+1 x1 >= 1\n".as_bytes()).is_err());
+        assert!(parse_opb_reader("+1 y1 >= 1 ;\n".as_bytes()).is_err());
+        assert!(parse_opb_reader("+1 x1 x2 >= 1 ;\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn wcnf_hard_and_soft_clauses() {
+        // Clauses weighing the top weight are hard in the old format
+        let state = parse_wcnf_reader("c old\np wcnf 3 4 10\n10 1 2 0\n10 -3 0\n4 -1 0\n0 3 0\n".as_bytes()).unwrap();
+        assert_eq!((state.vars(), state.original_clause_count), (3, 2));
+        assert_eq!(state.soft_clauses(), &[(4, vec![-1])]);
+
+        // Without a top weight every clause is soft
+        let state = parse_wcnf_reader("p wcnf 2 2\n1 1 0\n2 -1 2 0\n".as_bytes()).unwrap();
+        assert_eq!(state.original_clause_count, 0);
+        assert_eq!(state.soft_clauses().len(), 2);
+
+        let state = parse_wcnf_reader("c new\nh 1 -4 0\n3 2 3 0\n5 -1 0\n".as_bytes()).unwrap();
+        assert_eq!((state.vars(), state.original_clause_count), (4, 1));
+        assert_eq!(state.soft_clauses(), &[(3, vec![2, 3]), (5, vec![-1])]);
+
+        assert!(parse_wcnf_reader("p cnf 2 1\n1 2 0\n".as_bytes()).is_err());
+        assert!(parse_wcnf_reader("x 1 2 0\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn out_of_range_variables_are_rejected() {
+        assert!(parse_reader("p cnf 2 1\n1 -2 0\n".as_bytes()).is_ok());
+        assert!(parse_reader("p cnf 2 1\n1 5 0\n".as_bytes()).is_err());
+        assert!(parse_reader("p cnf 3 1\nx1 -3 0\n".as_bytes()).is_ok());
+        assert!(parse_reader("p cnf 3 1\nx1 -4 0\n".as_bytes()).is_err());
+        assert!(parse_reader("p cnf+ 3 1\n1 -3 <= 1\n".as_bytes()).is_ok());
+        assert!(parse_reader("p cnf+ 3 1\n1 -4 <= 1\n".as_bytes()).is_err());
+        assert!(parse_reader("c ind 1 3 0\np cnf 3 1\n1 0\n".as_bytes()).is_ok());
+        assert!(parse_reader("c ind 1 4 0\np cnf 3 1\n1 0\n".as_bytes()).is_err());
+    }
+}
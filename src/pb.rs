@@ -0,0 +1,303 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::assignment::Assignment;
+
+/// Linear pseudo-Boolean constraint: the coefficients of the true literals sum to at least the
+/// bound. Cardinality constraints are the ones with every coefficient 1.
+///
+/// Terms are kept with positive coefficients capped at the bound, one per variable, largest
+/// coefficient first.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pb {
+    pub terms: Vec<(u64, i32)>,
+    pub bound: u64,
+}
+
+impl Pb {
+    /// `sum coefficient * literal >= bound` for coefficients of any sign. A negative coefficient
+    /// moves to the negated literal and opposite literals of a variable are merged.
+    pub fn at_least(terms: Vec<(i64, i32)>, bound: i64) -> Pb {
+        // Coefficients of the positive literals, using not x = 1 - x
+        let mut coefficients: BTreeMap<usize, i64> = BTreeMap::new();
+        let mut bound = bound;
+        for (coefficient, literal) in terms {
+            let var = literal.unsigned_abs() as usize;
+            if literal > 0 {
+                *coefficients.entry(var).or_default() += coefficient;
+            } else {
+                *coefficients.entry(var).or_default() -= coefficient;
+                bound -= coefficient;
+            }
+        }
+
+        let mut normalized = Vec::new();
+        for (var, coefficient) in coefficients {
+            let literal = var as i32;
+            if coefficient > 0 {
+                normalized.push((coefficient, literal));
+            } else if coefficient < 0 {
+                normalized.push((-coefficient, -literal));
+                bound -= coefficient;
+            }
+        }
+        if bound <= 0 {
+            // Always satisfied
+            return Pb { terms: Vec::new(), bound: 0 };
+        }
+        let bound = bound as u64;
+        let mut terms: Vec<(u64, i32)> = normalized
+            .into_iter()
+            .map(|(coefficient, literal)| ((coefficient as u64).min(bound), literal))
+            .collect();
+        terms.sort_by_key(|(coefficient, literal)| (std::cmp::Reverse(*coefficient), literal.unsigned_abs()));
+        Pb { terms, bound }
+    }
+
+    /// `sum coefficient * literal <= bound` for coefficients of any sign
+    pub fn at_most(terms: Vec<(i64, i32)>, bound: i64) -> Pb {
+        Pb::at_least(terms.into_iter().map(|(coefficient, literal)| (-coefficient, literal)).collect(), -bound)
+    }
+
+    /// At least `k` of `literals` are true
+    pub fn cardinality_at_least(literals: &[i32], k: usize) -> Pb {
+        Pb::at_least(literals.iter().map(|literal| (1, *literal)).collect(), k as i64)
+    }
+
+    /// At most `k` of `literals` are true
+    pub fn cardinality_at_most(literals: &[i32], k: usize) -> Pb {
+        Pb::at_most(literals.iter().map(|literal| (1, *literal)).collect(), k as i64)
+    }
+
+    pub fn is_cardinality(&self) -> bool {
+        self.terms.iter().all(|(coefficient, _)| *coefficient == 1)
+    }
+
+    /// Clause explaining why the constraint implies `implied`, or why it is violated when that
+    /// is None, where `is_false` tells the literals that were false at that point.
+    ///
+    /// The implied literal comes first, followed by false literals with the largest
+    /// coefficients until the coefficients of the other literals cannot reach the bound.
+    pub fn explain(&self, implied: Option<i32>, is_false: impl Fn(i32) -> bool) -> Vec<i32> {
+        let total: u64 = self.terms.iter().map(|(coefficient, _)| coefficient).sum();
+        let (mut clause, mut rest) = match implied {
+            Some(literal) => {
+                let coefficient = self
+                    .terms
+                    .iter()
+                    .find(|(_, other)| *other == literal)
+                    .map_or(0, |(coefficient, _)| *coefficient);
+                (vec![literal], total - coefficient)
+            }
+            None => (Vec::new(), total),
+        };
+        for (coefficient, literal) in &self.terms {
+            if rest < self.bound {
+                break;
+            }
+            if is_false(*literal) {
+                clause.push(*literal);
+                rest -= coefficient;
+            }
+        }
+        clause
+    }
+
+    /// Unassigned literal that helps satisfy the constraint while the true literals fall short
+    /// of the bound
+    pub fn open_literal(&self, assignment: &Assignment) -> Option<i32> {
+        let satisfied: u64 = self
+            .terms
+            .iter()
+            .filter(|(_, literal)| assignment.value(*literal) == Some(true))
+            .map(|(coefficient, _)| coefficient)
+            .sum();
+        if satisfied >= self.bound {
+            return None;
+        }
+        self.terms
+            .iter()
+            .map(|(_, literal)| *literal)
+            .find(|literal| assignment.value(*literal).is_none())
+    }
+}
+
+/// Slack of every constraint, kept up to date as literals are assigned and unassigned so that
+/// propagation only looks at the constraints it changed. The slack is how far the coefficients
+/// of the literals that are not false exceed the bound: below zero the constraint is violated,
+/// and an unassigned literal whose coefficient exceeds it must be true.
+pub struct PbSlacks {
+    // Terms of each variable as (constraint, coefficient, literal)
+    occurs: Vec<Vec<(usize, u64, i32)>>,
+    slack: Vec<i64>,
+    // Constraints that may propagate or be violated since they were last looked at
+    pending: BTreeSet<usize>,
+}
+
+impl PbSlacks {
+    /// Slacks of `pbs` with every literal unassigned
+    pub fn new(pbs: &[Pb]) -> PbSlacks {
+        let mut occurs: Vec<Vec<(usize, u64, i32)>> = Vec::new();
+        let mut slack = Vec::new();
+        for (index, pb) in pbs.iter().enumerate() {
+            for (coefficient, literal) in &pb.terms {
+                let var = literal.unsigned_abs() as usize;
+                if occurs.len() <= var {
+                    occurs.resize(var + 1, Vec::new());
+                }
+                occurs[var].push((index, *coefficient, *literal));
+            }
+            let total: u64 = pb.terms.iter().map(|(coefficient, _)| coefficient).sum();
+            slack.push(total as i64 - pb.bound as i64);
+        }
+        PbSlacks {
+            occurs,
+            slack,
+            pending: (0..pbs.len()).collect(),
+        }
+    }
+
+    pub fn assign(&mut self, literal: i32) {
+        let Some(terms) = self.occurs.get(literal.unsigned_abs() as usize) else {
+            return;
+        };
+        for (index, coefficient, term) in terms {
+            if *term == -literal {
+                self.slack[*index] -= *coefficient as i64;
+                self.pending.insert(*index);
+            }
+        }
+    }
+
+    pub fn unassign(&mut self, literal: i32) {
+        let Some(terms) = self.occurs.get(literal.unsigned_abs() as usize) else {
+            return;
+        };
+        for (index, coefficient, term) in terms {
+            if *term == -literal {
+                self.slack[*index] += *coefficient as i64;
+            }
+            // The literal is open again and may be implied
+            self.pending.insert(*index);
+        }
+    }
+
+    /// First pending constraint that is violated, with None, or that implies an unassigned
+    /// literal under `assignment`, with that literal. Constraints doing neither stop pending.
+    pub fn propagate(&mut self, pbs: &[Pb], assignment: &Assignment) -> Option<(Option<i32>, usize)> {
+        while let Some(index) = self.pending.first().copied() {
+            let slack = self.slack[index];
+            if slack < 0 {
+                return Some((None, index));
+            }
+            // Terms are sorted by coefficient, so only a prefix can exceed the slack
+            let implied = pbs[index]
+                .terms
+                .iter()
+                .take_while(|(coefficient, _)| *coefficient as i64 > slack)
+                .map(|(_, literal)| *literal)
+                .find(|literal| assignment.value(*literal).is_none());
+            if implied.is_some() {
+                return Some((implied, index));
+            }
+            self.pending.remove(&index);
+        }
+        None
+    }
+}
+
+/// Linear function of literals to minimize, as given by the `min:` line of an OPB file
+#[derive(Clone, Debug, PartialEq)]
+pub struct Objective {
+    pub terms: Vec<(i64, i32)>,
+}
+
+impl Objective {
+    /// Sum of the coefficients of the true literals
+    pub fn value(&self, assignment: &Assignment) -> i64 {
+        self.terms
+            .iter()
+            .filter(|(_, literal)| assignment.value(*literal) == Some(true))
+            .map(|(coefficient, _)| coefficient)
+            .sum()
+    }
+
+    /// Gives the unassigned variables of the objective the values that lower it
+    pub fn complete(&self, assignment: &mut Assignment) {
+        for (coefficient, literal) in &self.terms {
+            if assignment.value(*literal).is_none() {
+                assignment.assign(if *coefficient < 0 { *literal } else { -literal });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num_bigint::BigUint;
+
+    use super::{Pb, PbSlacks};
+    use crate::assignment::Assignment;
+    use crate::count::count;
+    use crate::enumerate::models;
+    use crate::parser::parse_reader;
+    use crate::solver::solve;
+    use crate::testutil::brute_force_models;
+
+    #[test]
+    fn constraints_are_normalized_and_explained() {
+        // 3x1 - 2x2 + x3 - x1 >= 1 is 2x1 + 2(not x2) + x3 >= 3
+        let pb = Pb::at_least(vec![(3, 1), (-2, 2), (1, 3), (-1, 1)], 1);
+        assert_eq!(pb, Pb { terms: vec![(2, 1), (2, -2), (1, 3)], bound: 3 });
+        assert_eq!(Pb::cardinality_at_most(&[1, 2, 3], 1), Pb::cardinality_at_least(&[-1, -2, -3], 2));
+        assert!(Pb::at_most(vec![(1, 1)], 1).terms.is_empty());
+
+        // With x1 false both other literals must hold, the first one is explained by x1
+        let pbs = vec![pb.clone()];
+        let mut slacks = PbSlacks::new(&pbs);
+        slacks.assign(-1);
+        let assignment = Assignment::from_literals(&[-1], 3);
+        assert_eq!(slacks.propagate(&pbs, &assignment), Some((Some(-2), 0)));
+        assert_eq!(pb.explain(Some(-2), |literal| literal == 1), vec![-2, 1]);
+
+        slacks.assign(-3);
+        let assignment = Assignment::from_literals(&[-1, -3], 3);
+        assert_eq!(slacks.propagate(&pbs, &assignment), Some((None, 0)));
+        assert_eq!(pb.explain(None, |literal| literal == 1 || literal == 3), vec![1, 3]);
+
+        slacks.unassign(-3);
+        slacks.unassign(-1);
+        slacks.assign(1);
+        let assignment = Assignment::from_literals(&[1], 3);
+        assert_eq!(slacks.propagate(&pbs, &assignment), None);
+        assert_eq!(pb.open_literal(&assignment), Some(-2));
+    }
+
+    #[test]
+    fn search_respects_constraints() {
+        let input = "p cnf+ 6 4\n1 2 3 4 <= 2\n-3 -4 5 6 >= 3\n1 5 0\n-2 6 0\n";
+        let mut state = parse_reader(input.as_bytes()).unwrap();
+        assert_eq!(state.original_clause_count, 2);
+        state.add_pb(Pb::at_least(vec![(3, 1), (2, 2), (2, -6), (1, 5)], 4));
+        let constraints = state.pbs().to_vec();
+        assert_eq!(constraints.len(), 3);
+
+        let mut expected = brute_force_models(&state);
+        expected.sort();
+
+        let mut found: Vec<Vec<i32>> = models(&mut state).collect();
+        found.sort();
+        assert_eq!(found, expected);
+        assert!(state.stats().pb_explanations > 0);
+
+        // Explanations only serve conflict analysis and are never kept as clauses
+        let mut state = parse_reader(input.as_bytes()).unwrap();
+        state.add_pb(constraints[2].clone());
+        assert!(solve(&mut state));
+        let stats = state.stats();
+        assert_eq!(state.clauses(), state.original_clause_count + stats.learned_clauses - stats.deleted_clauses);
+
+        let mut state = parse_reader(input.as_bytes()).unwrap();
+        state.add_pb(constraints[2].clone());
+        assert_eq!(count(&mut state), BigUint::from(expected.len()));
+    }
+}
@@ -0,0 +1,167 @@
+use crate::clause::Clause;
+use crate::reconstruction::Reconstruction;
+use crate::solver_state::SolverState;
+
+mod blocked;
+mod elimination;
+mod equivalence;
+mod probing;
+mod subsumption;
+
+pub use subsumption::{check_subsumption, Subsumption};
+
+// Position of a literal in the occurrence lists, the two polarities of a variable are adjacent
+pub(crate) fn lit_index(literal: i32) -> usize {
+    2 * literal.unsigned_abs() as usize + (literal < 0) as usize
+}
+
+/// Clause database the preprocessing passes work on before search.
+///
+/// Clauses are never moved, removing one only marks it, so indices in the occurrence lists stay
+/// valid. Every clause that is removed without being implied by the rest of the formula is pushed
+/// to the reconstruction stack.
+pub struct Preprocessor {
+    clauses: Vec<Vec<i32>>,
+    removed: Vec<bool>,
+    // Clauses containing each literal, by `lit_index`
+    occurs: Vec<Vec<usize>>,
+    vars: usize,
+    eliminated: Vec<bool>,
+    reconstruction: Reconstruction,
+}
+
+impl Preprocessor {
+    /// Takes the clauses of a state that has not started searching. Repeated literals are
+    /// dropped and tautologies are left out.
+    pub fn new(state: &SolverState) -> Preprocessor {
+        let mut preprocessor = Preprocessor {
+            clauses: Vec::new(),
+            removed: Vec::new(),
+            occurs: vec![Vec::new(); 2 * state.vars() + 2],
+            vars: state.vars(),
+            eliminated: vec![false; state.vars() + 1],
+            reconstruction: state.reconstruction().clone(),
+        };
+        for clause in state.clauselist() {
+            let mut literals: Vec<i32> = Vec::new();
+            for literal in &clause.vars {
+                if !literals.contains(literal) {
+                    literals.push(*literal);
+                }
+            }
+            if !literals.iter().any(|literal| literals.contains(&-literal)) {
+                preprocessor.add_clause(literals);
+            }
+        }
+        preprocessor
+    }
+
+    pub fn vars(&self) -> usize {
+        self.vars
+    }
+
+    pub fn is_eliminated(&self, var: usize) -> bool {
+        self.eliminated[var]
+    }
+
+    /// Indices of the remaining clauses containing `literal`
+    pub fn occurrences(&self, literal: i32) -> &[usize] {
+        &self.occurs[lit_index(literal)]
+    }
+
+    pub fn clause(&self, index: usize) -> &[i32] {
+        &self.clauses[index]
+    }
+
+    /// Indices of all clauses not removed so far
+    pub fn active_clauses(&self) -> Vec<usize> {
+        (0..self.clauses.len()).filter(|index| !self.removed[*index]).collect()
+    }
+
+    pub fn add_clause(&mut self, literals: Vec<i32>) -> usize {
+        let index = self.clauses.len();
+        for literal in &literals {
+            self.occurs[lit_index(*literal)].push(index);
+        }
+        self.clauses.push(literals);
+        self.removed.push(false);
+        index
+    }
+
+    /// Drops a clause that is implied by the remaining formula
+    pub fn remove_clause(&mut self, index: usize) {
+        self.removed[index] = true;
+        for literal in &self.clauses[index] {
+            self.occurs[lit_index(*literal)].retain(|other| *other != index);
+        }
+    }
+
+    /// Removes a literal from a clause that still holds without it
+    pub fn remove_literal(&mut self, index: usize, literal: i32) {
+        self.clauses[index].retain(|other| *other != literal);
+        self.occurs[lit_index(literal)].retain(|other| *other != index);
+    }
+
+    /// Drops a clause the remaining formula does not imply, keeping it for model reconstruction
+    /// with `witness` as the literal to flip when it ends up falsified
+    pub fn remove_with_witness(&mut self, index: usize, witness: i32) {
+        self.reconstruction.push(witness, self.clauses[index].clone());
+        self.remove_clause(index);
+    }
+
+    /// Writes the remaining clauses back as the original clauses of the state
+    pub fn apply(self, state: &mut SolverState) {
+        let clauses = self
+            .active_clauses()
+            .into_iter()
+            .map(|index| Clause::from_vec(self.clauses[index].clone()))
+            .collect();
+        state.replace_clauses(clauses);
+        state.set_reconstruction(self.reconstruction);
+    }
+}
+
+/// Runs the preprocessing passes enabled in the state's configuration
+pub fn preprocess(state: &mut SolverState) {
+    let mut config = state.config().clone();
+    if !state.is_clausal() || !state.soft_clauses().is_empty() {
+        // These passes rely on seeing every occurrence of a variable, and XOR and
+        // pseudo-Boolean constraints and soft clauses are not among the clauses
+        config.preserve_models();
+    }
+    if state.independent_support().is_some() {
+        // The formula keeps its projection, so it must keep the models projected onto it
+        config.preserve_models();
+    }
+    let mut preprocessor = Preprocessor::new(state);
+    preprocessor.simplify_units();
+    if config.probe {
+        state.stats_mut().failed_literals += preprocessor.probe();
+        preprocessor.simplify_units();
+    }
+    if config.substitute {
+        state.stats_mut().substituted_variables += preprocessor.substitute_equivalences();
+        preprocessor.simplify_units();
+    }
+    if config.subsume {
+        subsume(&mut preprocessor, state);
+    }
+    if config.eliminate {
+        let eliminated = preprocessor.eliminate_variables(config.elim_occurrence_limit, config.elim_clause_limit);
+        state.stats_mut().eliminated_variables += eliminated;
+        // Resolvents are often subsumed by each other or by clauses that were kept
+        if config.subsume {
+            subsume(&mut preprocessor, state);
+        }
+    }
+    if config.block {
+        state.stats_mut().blocked_clauses += preprocessor.eliminate_blocked(config.covered);
+    }
+    preprocessor.apply(state);
+}
+
+fn subsume(preprocessor: &mut Preprocessor, state: &mut SolverState) {
+    let (clauses, literals) = preprocessor.subsume();
+    state.stats_mut().subsumed_clauses += clauses;
+    state.stats_mut().strengthened_literals += literals;
+}
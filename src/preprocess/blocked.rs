@@ -0,0 +1,138 @@
+use super::Preprocessor;
+
+// Whether resolving `clause` with `other` on `literal` gives a tautology
+fn tautological_resolvent(clause: &[i32], other: &[i32], literal: i32) -> bool {
+    other.iter().any(|partner| *partner != -literal && clause.contains(&-partner))
+}
+
+impl Preprocessor {
+    /// Literal of `clause` whose every resolvent with the remaining clauses is a tautology
+    fn blocking_literal(&self, clause: &[i32]) -> Option<i32> {
+        clause.iter().copied().find(|literal| {
+            self.occurrences(-literal)
+                .iter()
+                .all(|other| tautological_resolvent(clause, self.clause(*other), *literal))
+        })
+    }
+
+    // Literals occurring in every non-tautological resolution partner of `clause` on `literal`
+    fn covered_literals(&self, clause: &[i32], literal: i32) -> Vec<i32> {
+        let mut covered: Option<Vec<i32>> = None;
+        for other in self.occurrences(-literal) {
+            let other = self.clause(*other);
+            if tautological_resolvent(clause, other, literal) {
+                continue;
+            }
+            let candidates = other
+                .iter()
+                .copied()
+                .filter(|partner| *partner != -literal && !clause.contains(partner));
+            covered = Some(match covered {
+                None => candidates.collect(),
+                Some(previous) => candidates.filter(|partner| previous.contains(partner)).collect(),
+            });
+            if covered.as_ref().is_some_and(|covered| covered.is_empty()) {
+                break;
+            }
+        }
+        covered.unwrap_or_default()
+    }
+
+    /// Covered clause elimination for one clause: adds covered literals until the extended clause
+    /// is blocked, then removes the clause. Every extension step is kept for reconstruction, as
+    /// the witness of a step repairs the clause before it once the extended clause is satisfied.
+    fn eliminate_covered(&mut self, index: usize) -> bool {
+        let mut extended = self.clauses[index].clone();
+        let mut steps = Vec::new();
+        loop {
+            if let Some(blocking) = self.blocking_literal(&extended) {
+                self.remove_clause(index);
+                for (witness, clause) in steps {
+                    self.reconstruction.push(witness, clause);
+                }
+                self.reconstruction.push(blocking, extended);
+                return true;
+            }
+            let step = extended.iter().find_map(|literal| {
+                let covered = self.covered_literals(&extended, *literal);
+                (!covered.is_empty()).then_some((*literal, covered))
+            });
+            match step {
+                Some((literal, covered)) => {
+                    steps.push((literal, extended.clone()));
+                    extended.extend(covered);
+                }
+                None => return false,
+            }
+        }
+    }
+
+    /// Blocked clause elimination, and covered clause elimination if `covered` is set, repeated
+    /// until no more clauses can be removed. Returns the number of clauses removed.
+    pub fn eliminate_blocked(&mut self, covered: bool) -> usize {
+        let mut removed = 0;
+        loop {
+            let before = removed;
+            for index in self.active_clauses() {
+                let clause = self.clauses[index].clone();
+                if let Some(literal) = self.blocking_literal(&clause) {
+                    self.remove_with_witness(index, literal);
+                    removed += 1;
+                } else if covered && self.eliminate_covered(index) {
+                    removed += 1;
+                }
+            }
+            if removed == before {
+                return removed;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::parse_reader;
+    use crate::preprocess::Preprocessor;
+    use crate::solver::solve;
+    use crate::solver_state::SolverState;
+
+    fn satisfies_original(state: &SolverState, input: &str) -> bool {
+        let model = state.model();
+        let original = parse_reader(input.as_bytes()).unwrap();
+        original
+            .clauselist()
+            .iter()
+            .all(|clause| clause.vars.iter().any(|literal| model.value(*literal) == Some(true)))
+    }
+
+    #[test]
+    fn blocked_clauses_are_removed_and_reconstructed() {
+        // (1 2) is blocked on 1, after which the others become blocked in turn
+        let input = "p cnf 3 3\n1 2 0\n-1 -2 0\n1 3 0\n";
+        let mut state = parse_reader(input.as_bytes()).unwrap();
+        let mut preprocessor = Preprocessor::new(&state);
+        assert_eq!(preprocessor.eliminate_blocked(false), 3);
+        preprocessor.apply(&mut state);
+        assert!(solve(&mut state));
+        assert!(satisfies_original(&state, input));
+
+        // Every clause of this unsatisfiable formula is needed
+        let input = "p cnf 2 4\n1 2 0\n-1 -2 0\n1 -2 0\n-1 2 0\n";
+        let state = parse_reader(input.as_bytes()).unwrap();
+        assert_eq!(Preprocessor::new(&state).eliminate_blocked(true), 0);
+    }
+
+    #[test]
+    fn covered_clause_is_removed() {
+        // (1 2) is not blocked, but -3 is covered on 1 and (1 2 -3) is blocked on 2
+        let input = "p cnf 3 3\n1 2 0\n-2 3 0\n-3 -1 0\n";
+        let mut state = parse_reader(input.as_bytes()).unwrap();
+        let mut preprocessor = Preprocessor::new(&state);
+        assert_eq!(preprocessor.blocking_literal(&[1, 2]), None);
+        assert!(preprocessor.eliminate_covered(0));
+        assert_eq!(preprocessor.reconstruction.entries(), &[(1, vec![1, 2]), (2, vec![1, 2, -3])]);
+        preprocessor.apply(&mut state);
+        assert!(solve(&mut state));
+        assert!(satisfies_original(&state, input));
+    }
+}
@@ -0,0 +1,108 @@
+use super::Preprocessor;
+
+// Resolvent of two clauses on `var`, or None if it is a tautology
+fn resolve(positive: &[i32], negative: &[i32], var: i32) -> Option<Vec<i32>> {
+    let mut resolvent: Vec<i32> = positive.iter().copied().filter(|literal| *literal != var).collect();
+    for literal in negative {
+        if *literal == -var || resolvent.contains(literal) {
+            continue;
+        }
+        if resolvent.contains(&-literal) {
+            return None;
+        }
+        resolvent.push(*literal);
+    }
+    Some(resolvent)
+}
+
+impl Preprocessor {
+    /// Bounded variable elimination: replaces the clauses of a variable by all their resolvents on
+    /// it whenever that does not increase the number of clauses. Variables occurring more than
+    /// `occurrence_limit` times or producing a resolvent longer than `clause_limit` are kept.
+    /// Returns the number of variables eliminated.
+    pub fn eliminate_variables(&mut self, occurrence_limit: usize, clause_limit: usize) -> usize {
+        // Cheapest variables first, as in SatELite
+        let mut candidates: Vec<usize> = (1..=self.vars).collect();
+        candidates.sort_by_key(|var| {
+            let var = *var as i32;
+            (self.occurrences(var).len() * self.occurrences(-var).len(), var)
+        });
+
+        let mut eliminated = 0;
+        for var in candidates {
+            if self.try_eliminate(var as i32, occurrence_limit, clause_limit) {
+                eliminated += 1;
+            }
+        }
+        eliminated
+    }
+
+    fn try_eliminate(&mut self, var: i32, occurrence_limit: usize, clause_limit: usize) -> bool {
+        let positive = self.occurrences(var).to_vec();
+        let negative = self.occurrences(-var).to_vec();
+        let occurrences = positive.len() + negative.len();
+        if occurrences == 0 || occurrences > occurrence_limit {
+            return false;
+        }
+
+        let mut resolvents = Vec::new();
+        for pos in &positive {
+            for neg in &negative {
+                if let Some(resolvent) = resolve(self.clause(*pos), self.clause(*neg), var) {
+                    if resolvent.len() > clause_limit || resolvents.len() == occurrences {
+                        return false;
+                    }
+                    resolvents.push(resolvent);
+                }
+            }
+        }
+
+        // Both sides are kept so the variable can be fixed whatever value it was given
+        for index in positive {
+            self.remove_with_witness(index, var);
+        }
+        for index in negative {
+            self.remove_with_witness(index, -var);
+        }
+        for resolvent in resolvents {
+            self.add_clause(resolvent);
+        }
+        self.eliminated[var as usize] = true;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::resolve;
+    use crate::parser::parse_reader;
+    use crate::preprocess::Preprocessor;
+    use crate::solver::solve;
+
+    #[test]
+    fn resolvents_skip_tautologies() {
+        assert_eq!(resolve(&[1, 2], &[-1, 3, 2], 1), Some(vec![2, 3]));
+        assert_eq!(resolve(&[1, 2], &[-1, -2], 1), None);
+    }
+
+    #[test]
+    fn eliminated_variables_are_reconstructed() {
+        // 4 only links two clauses and is eliminated, eliminating 1 would grow the formula
+        let input = "p cnf 5 7\n1 4 0\n2 -4 0\n-1 -2 0\n1 3 0\n1 -3 5 0\n1 2 -5 0\n-1 3 5 0\n";
+        let mut state = parse_reader(input.as_bytes()).unwrap();
+        let mut preprocessor = Preprocessor::new(&state);
+        let eliminated = preprocessor.eliminate_variables(6, 20);
+        assert!(eliminated > 0);
+        assert!(preprocessor.is_eliminated(4));
+        assert!(!preprocessor.is_eliminated(1));
+        preprocessor.apply(&mut state);
+        assert!(state.clauselist().iter().all(|clause| !clause.vars.contains(&4) && !clause.vars.contains(&-4)));
+
+        assert!(solve(&mut state));
+        let model = state.model();
+        let original = parse_reader(input.as_bytes()).unwrap();
+        for clause in original.clauselist() {
+            assert!(clause.vars.iter().any(|literal| model.value(*literal) == Some(true)));
+        }
+    }
+}
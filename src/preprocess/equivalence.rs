@@ -0,0 +1,184 @@
+use super::{lit_index, Preprocessor};
+
+// Marks literals not yet visited by Tarjan's algorithm
+const UNVISITED: usize = usize::MAX;
+
+impl Preprocessor {
+    // Literals implied by `literal` through a binary clause, by `lit_index`
+    fn implication_graph(&self) -> Vec<Vec<i32>> {
+        let mut graph = vec![Vec::new(); 2 * self.vars + 2];
+        for index in self.active_clauses() {
+            if let [first, second] = self.clauses[index][..] {
+                graph[lit_index(-first)].push(second);
+                graph[lit_index(-second)].push(first);
+            }
+        }
+        graph
+    }
+
+    /// Strongly connected components of the binary implication graph with more than one
+    /// literal, found with an iterative version of Tarjan's algorithm
+    pub fn equivalent_literals(&self) -> Vec<Vec<i32>> {
+        let graph = self.implication_graph();
+        let mut index = vec![UNVISITED; graph.len()];
+        let mut lowlink = vec![0; graph.len()];
+        let mut on_stack = vec![false; graph.len()];
+        let mut stack: Vec<i32> = Vec::new();
+        let mut components = Vec::new();
+        let mut counter = 0;
+
+        for var in 1..=self.vars as i32 {
+            for start in [var, -var] {
+                if index[lit_index(start)] != UNVISITED {
+                    continue;
+                }
+                // Literals being explored with the position of their next successor
+                let mut work = vec![(start, 0)];
+                while let Some((literal, position)) = work.pop() {
+                    let node = lit_index(literal);
+                    if position == 0 {
+                        index[node] = counter;
+                        lowlink[node] = counter;
+                        counter += 1;
+                        stack.push(literal);
+                        on_stack[node] = true;
+                    }
+                    if let Some(next) = graph[node].get(position) {
+                        work.push((literal, position + 1));
+                        let next_node = lit_index(*next);
+                        if index[next_node] == UNVISITED {
+                            work.push((*next, 0));
+                        } else if on_stack[next_node] {
+                            lowlink[node] = lowlink[node].min(index[next_node]);
+                        }
+                        continue;
+                    }
+
+                    if lowlink[node] == index[node] {
+                        let mut component = Vec::new();
+                        loop {
+                            let member = stack.pop().unwrap();
+                            on_stack[lit_index(member)] = false;
+                            component.push(member);
+                            if member == literal {
+                                break;
+                            }
+                        }
+                        if component.len() > 1 {
+                            components.push(component);
+                        }
+                    }
+                    if let Some((parent, _)) = work.last() {
+                        let parent = lit_index(*parent);
+                        lowlink[parent] = lowlink[parent].min(lowlink[node]);
+                    }
+                }
+            }
+        }
+        components
+    }
+
+    /// Equivalent literal substitution: replaces every literal equivalent to another through
+    /// binary clauses by the one with the lowest variable. The equivalences are kept for model
+    /// reconstruction. Returns the number of variables substituted.
+    pub fn substitute_equivalences(&mut self) -> usize {
+        // Literal each variable is replaced by, or 0 for variables that are kept
+        let mut replacement = vec![0; self.vars + 1];
+        for component in self.equivalent_literals() {
+            if component.iter().any(|literal| component.contains(&-literal)) {
+                // A literal equivalent to its own negation, the formula is unsatisfiable
+                self.add_clause(Vec::new());
+                return 0;
+            }
+            let representative = *component.iter().min_by_key(|literal| literal.unsigned_abs()).unwrap();
+            for literal in &component {
+                let var = literal.unsigned_abs() as usize;
+                if *literal != representative && replacement[var] == 0 {
+                    replacement[var] = if *literal > 0 { representative } else { -representative };
+                }
+            }
+        }
+        let substitute = |literal: i32| match replacement[literal.unsigned_abs() as usize] {
+            0 => literal,
+            other if literal > 0 => other,
+            other => -other,
+        };
+
+        for index in self.active_clauses() {
+            if self.clauses[index].iter().all(|literal| substitute(*literal) == *literal) {
+                continue;
+            }
+            let mut substituted: Vec<i32> = Vec::new();
+            for literal in &self.clauses[index] {
+                let literal = substitute(*literal);
+                if !substituted.contains(&literal) {
+                    substituted.push(literal);
+                }
+            }
+            self.remove_clause(index);
+            if !substituted.iter().any(|literal| substituted.contains(&-literal)) {
+                self.add_clause(substituted);
+            }
+        }
+
+        let mut substituted = 0;
+        for (var, representative) in replacement.into_iter().enumerate() {
+            if representative != 0 {
+                let literal = var as i32;
+                self.reconstruction.push(literal, vec![literal, -representative]);
+                self.reconstruction.push(-literal, vec![-literal, representative]);
+                self.eliminated[var] = true;
+                substituted += 1;
+            }
+        }
+        substituted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::parse_reader;
+    use crate::preprocess::Preprocessor;
+    use crate::solver::solve;
+
+    #[test]
+    fn equivalence_chains_are_substituted() {
+        // 1 = -2 = 3 through binary clauses, 4 only implies 1
+        let input = "p cnf 5 7\n1 2 0\n-1 -2 0\n2 3 0\n-2 -3 0\n-4 1 0\n-3 4 5 0\n-1 -5 0\n";
+        let mut state = parse_reader(input.as_bytes()).unwrap();
+        let mut preprocessor = Preprocessor::new(&state);
+        let mut components = preprocessor.equivalent_literals();
+        for component in &mut components {
+            component.sort();
+        }
+        components.sort();
+        assert_eq!(components, vec![vec![-3, -1, 2], vec![-2, 1, 3]]);
+
+        assert_eq!(preprocessor.substitute_equivalences(), 2);
+        assert!(preprocessor.is_eliminated(2) && preprocessor.is_eliminated(3));
+        let remaining: Vec<&[i32]> = preprocessor
+            .active_clauses()
+            .into_iter()
+            .map(|index| preprocessor.clause(index))
+            .collect();
+        assert_eq!(remaining, vec![&[-4, 1][..], &[-1, -5][..], &[-1, 4, 5][..]]);
+
+        preprocessor.apply(&mut state);
+        assert!(solve(&mut state));
+        let model = state.model();
+        let original = parse_reader(input.as_bytes()).unwrap();
+        for clause in original.clauselist() {
+            assert!(clause.vars.iter().any(|literal| model.value(*literal) == Some(true)));
+        }
+    }
+
+    #[test]
+    fn contradictory_equivalence_is_unsatisfiable() {
+        let input = "p cnf 2 4\n1 2 0\n-1 -2 0\n1 -2 0\n-1 2 0\n";
+        let mut state = parse_reader(input.as_bytes()).unwrap();
+        let mut preprocessor = Preprocessor::new(&state);
+        preprocessor.substitute_equivalences();
+        preprocessor.apply(&mut state);
+        assert!(!solve(&mut state));
+    }
+}
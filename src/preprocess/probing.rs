@@ -0,0 +1,165 @@
+use super::Preprocessor;
+
+impl Preprocessor {
+    /// Unit propagation over the remaining clauses from the unit clauses of the formula and
+    /// `literals`. Returns every literal that ends up true, or None on a conflict.
+    pub fn propagate(&self, literals: &[i32]) -> Option<Vec<i32>> {
+        let mut value: Vec<Option<bool>> = vec![None; self.vars + 1];
+        let mut trail: Vec<i32> = Vec::new();
+        let mut assign = |literal: i32, value: &mut Vec<Option<bool>>| {
+            match value[literal.unsigned_abs() as usize] {
+                Some(val) => val == (literal > 0),
+                None => {
+                    value[literal.unsigned_abs() as usize] = Some(literal > 0);
+                    trail.push(literal);
+                    true
+                }
+            }
+        };
+
+        for index in self.active_clauses() {
+            match self.clauses[index][..] {
+                [] => return None,
+                [unit] if !assign(unit, &mut value) => return None,
+                _ => (),
+            }
+        }
+        for literal in literals {
+            if !assign(*literal, &mut value) {
+                return None;
+            }
+        }
+
+        let mut next = 0;
+        while next < trail.len() {
+            let literal = trail[next];
+            next += 1;
+            for index in self.occurrences(-literal) {
+                let mut unassigned = None;
+                let mut open = 0;
+                let mut satisfied = false;
+                for other in &self.clauses[*index] {
+                    match value[other.unsigned_abs() as usize] {
+                        Some(val) if val == (*other > 0) => {
+                            satisfied = true;
+                            break;
+                        }
+                        Some(_) => (),
+                        None => {
+                            open += 1;
+                            unassigned = Some(*other);
+                        }
+                    }
+                }
+                if satisfied || open > 1 {
+                    continue;
+                }
+                match unassigned {
+                    Some(implied) => {
+                        value[implied.unsigned_abs() as usize] = Some(implied > 0);
+                        trail.push(implied);
+                    }
+                    None => return None,
+                }
+            }
+        }
+        Some(trail)
+    }
+
+    /// Removes clauses satisfied by the literals fixed at the top level and false literals from
+    /// the others, keeping one unit clause per fixed literal. An unsatisfiable formula is reduced
+    /// to the empty clause.
+    pub fn simplify_units(&mut self) {
+        let fixed = match self.propagate(&[]) {
+            Some(fixed) => fixed,
+            None => {
+                for index in self.active_clauses() {
+                    self.remove_clause(index);
+                }
+                self.add_clause(Vec::new());
+                return;
+            }
+        };
+        let mut value: Vec<Option<bool>> = vec![None; self.vars + 1];
+        for literal in &fixed {
+            value[literal.unsigned_abs() as usize] = Some(*literal > 0);
+        }
+
+        for index in self.active_clauses() {
+            let clause = self.clauses[index].clone();
+            if clause.iter().any(|literal| value[literal.unsigned_abs() as usize] == Some(*literal > 0)) {
+                self.remove_clause(index);
+                continue;
+            }
+            for literal in clause {
+                if value[literal.unsigned_abs() as usize].is_some() {
+                    self.remove_literal(index, literal);
+                }
+            }
+        }
+        for literal in fixed {
+            self.add_clause(vec![literal]);
+        }
+    }
+
+    /// Failed literal probing: assumes each literal with binary implications at the top level and
+    /// learns its negation as a unit when propagation runs into a conflict. Returns the number of
+    /// failed literals found.
+    pub fn probe(&mut self) -> usize {
+        let mut failed = 0;
+        let mut fixed = match self.propagate(&[]) {
+            Some(fixed) => fixed,
+            None => return failed,
+        };
+        for var in 1..=self.vars as i32 {
+            if self.is_eliminated(var as usize) {
+                continue;
+            }
+            for literal in [var, -var] {
+                if fixed.iter().any(|other| other.abs() == var) {
+                    break;
+                }
+                let implies = self
+                    .occurrences(-literal)
+                    .iter()
+                    .any(|index| self.clauses[*index].len() == 2);
+                if !implies || self.propagate(&[literal]).is_some() {
+                    continue;
+                }
+                failed += 1;
+                self.add_clause(vec![-literal]);
+                fixed = match self.propagate(&[]) {
+                    Some(fixed) => fixed,
+                    // Both polarities fail, the formula is unsatisfiable
+                    None => return failed,
+                };
+            }
+        }
+        failed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::parse_reader;
+    use crate::preprocess::Preprocessor;
+
+    #[test]
+    fn failed_literals_become_units() {
+        // 1 implies both 2 and -2 through binary clauses
+        let input = "p cnf 4 4\n-1 2 0\n-1 3 0\n-3 -2 0\n1 3 4 0\n";
+        let state = parse_reader(input.as_bytes()).unwrap();
+        let mut preprocessor = Preprocessor::new(&state);
+        assert_eq!(preprocessor.propagate(&[1]), None);
+        assert_eq!(preprocessor.propagate(&[-3]), Some(vec![-3, -1, 4]));
+
+        assert_eq!(preprocessor.probe(), 1);
+        preprocessor.simplify_units();
+        let remaining: Vec<&[i32]> = preprocessor
+            .active_clauses()
+            .into_iter()
+            .map(|index| preprocessor.clause(index))
+            .collect();
+        assert_eq!(remaining, vec![&[-3, -2][..], &[3, 4][..], &[-1][..]]);
+    }
+}
@@ -0,0 +1,110 @@
+use std::cmp::Reverse;
+
+use super::Preprocessor;
+
+/// How a clause simplifies another clause it is checked against
+#[derive(Debug, PartialEq)]
+pub enum Subsumption {
+    // Every literal of the clause occurs in the other one, which is redundant
+    Subsumed,
+    // Self-subsuming resolution: the other clause holds without this literal
+    Strengthened(i32),
+}
+
+/// Checks whether `clause` subsumes `other`, or would after negating exactly one of its literals
+pub fn check_subsumption(clause: &[i32], other: &[i32]) -> Option<Subsumption> {
+    if clause.len() > other.len() {
+        return None;
+    }
+    let mut flipped = None;
+    for literal in clause {
+        if other.contains(literal) {
+            continue;
+        }
+        if flipped.is_none() && other.contains(&-literal) {
+            flipped = Some(-literal);
+            continue;
+        }
+        return None;
+    }
+    match flipped {
+        Some(literal) => Some(Subsumption::Strengthened(literal)),
+        None => Some(Subsumption::Subsumed),
+    }
+}
+
+impl Preprocessor {
+    /// Backward subsumption and self-subsuming strengthening until neither applies.
+    /// Returns the number of clauses removed and literals removed from clauses.
+    pub fn subsume(&mut self) -> (usize, usize) {
+        // Popped shortest first, so clauses are used to simplify before being simplified
+        let mut queue = self.active_clauses();
+        queue.sort_by_key(|index| Reverse((self.clauses[*index].len(), *index)));
+
+        let mut removed_clauses = 0;
+        let mut removed_literals = 0;
+        while let Some(index) = queue.pop() {
+            if self.removed[index] || self.clauses[index].is_empty() {
+                continue;
+            }
+            let clause = self.clauses[index].clone();
+            // Any clause it simplifies contains its least frequent variable
+            let pivot = *clause
+                .iter()
+                .min_by_key(|literal| self.occurrences(**literal).len() + self.occurrences(-**literal).len())
+                .unwrap();
+            let mut candidates = self.occurrences(pivot).to_vec();
+            candidates.extend_from_slice(self.occurrences(-pivot));
+
+            for other in candidates {
+                if other == index || self.removed[other] {
+                    continue;
+                }
+                match check_subsumption(&clause, &self.clauses[other]) {
+                    Some(Subsumption::Subsumed) => {
+                        self.remove_clause(other);
+                        removed_clauses += 1;
+                    }
+                    Some(Subsumption::Strengthened(literal)) => {
+                        self.remove_literal(other, literal);
+                        removed_literals += 1;
+                        queue.push(other);
+                    }
+                    None => (),
+                }
+            }
+        }
+        (removed_clauses, removed_literals)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_subsumption, Subsumption};
+    use crate::parser::parse_reader;
+    use crate::preprocess::Preprocessor;
+
+    #[test]
+    fn subsumption_checks() {
+        assert_eq!(check_subsumption(&[1, 2], &[3, 2, 1]), Some(Subsumption::Subsumed));
+        assert_eq!(check_subsumption(&[1, 2], &[-1, 2, 3]), Some(Subsumption::Strengthened(-1)));
+        assert_eq!(check_subsumption(&[1, 2], &[-1, -2, 3]), None);
+        assert_eq!(check_subsumption(&[1, 2, 3], &[1, 2]), None);
+    }
+
+    #[test]
+    fn duplicates_and_supersets_are_removed() {
+        // (1 2) removes its duplicate and (1 2 3), strengthens (-1 2 4) to (2 4),
+        // which in turn removes (2 4 5)
+        let input = "p cnf 5 5\n1 2 0\n2 1 0\n1 2 3 0\n-1 2 4 0\n2 4 5 0\n";
+        let state = parse_reader(input.as_bytes()).unwrap();
+        let mut preprocessor = Preprocessor::new(&state);
+        assert_eq!(preprocessor.subsume(), (3, 1));
+        let remaining: Vec<&[i32]> = preprocessor
+            .active_clauses()
+            .into_iter()
+            .map(|index| preprocessor.clause(index))
+            .collect();
+        assert_eq!(remaining, vec![&[1, 2][..], &[2, 4][..]]);
+    }
+}
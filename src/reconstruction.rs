@@ -0,0 +1,141 @@
+use std::error::Error;
+use std::io::{self, BufRead, Write};
+
+use crate::assignment::Assignment;
+
+/// Clauses removed by preprocessing, each with the literal that can be flipped to satisfy it.
+///
+/// A model of the simplified formula is extended to the original formula by walking the stack
+/// from the most recently removed clause back, making the witness true whenever its clause is
+/// not yet satisfied.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Reconstruction {
+    stack: Vec<(i32, Vec<i32>)>,
+}
+
+impl Reconstruction {
+    pub fn new() -> Reconstruction {
+        Reconstruction { stack: Vec::new() }
+    }
+
+    pub fn push(&mut self, witness: i32, clause: Vec<i32>) {
+        self.stack.push((witness, clause));
+    }
+
+    pub fn len(&self) -> usize {
+        self.stack.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stack.is_empty()
+    }
+
+    /// Witnesses and clauses in the order they were removed
+    pub fn entries(&self) -> &[(i32, Vec<i32>)] {
+        &self.stack
+    }
+
+    /// Turns a model of the simplified formula into a model of the original one.
+    /// Variables of removed clauses left unassigned by the search are fixed to false first.
+    pub fn extend(&self, assignment: &mut Assignment) {
+        for (_, clause) in &self.stack {
+            for literal in clause {
+                if assignment.value(*literal).is_none() {
+                    assignment.assign(-literal.abs());
+                }
+            }
+        }
+        for (witness, clause) in self.stack.iter().rev() {
+            if !clause.iter().any(|literal| assignment.value(*literal) == Some(true)) {
+                assignment.assign(*witness);
+            }
+        }
+    }
+
+    /// Writes the stack for `vars` variables as a `p rec` header followed by one line per removed
+    /// clause, witness first, in DIMACS style
+    pub fn write<W: Write>(&self, vars: usize, out: &mut W) -> io::Result<()> {
+        writeln!(out, "p rec {} {}", vars, self.stack.len())?;
+        for (witness, clause) in &self.stack {
+            write!(out, "{witness}")?;
+            for literal in clause.iter().filter(|literal| *literal != witness) {
+                write!(out, " {literal}")?;
+            }
+            writeln!(out, " 0")?;
+        }
+        Ok(())
+    }
+
+    /// Reads a stack written by `write`, returning it with its number of variables
+    pub fn read<R: BufRead>(reader: R) -> Result<(Reconstruction, usize), Box<dyn Error>> {
+        let mut reconstruction = Reconstruction::new();
+        let mut vars = None;
+        for line in reader.lines() {
+            let line = line?;
+            if line.starts_with('c') || line.trim().is_empty() {
+                continue;
+            }
+            let splits: Vec<&str> = line.split_whitespace().collect();
+            if splits[0] == "p" {
+                if splits.len() != 4 || splits[1] != "rec" {
+                    return Err("Bad reconstruction header".into());
+                }
+                vars = Some(splits[2].parse::<usize>().map_err(|_| "Variable count must be a number")?);
+                continue;
+            }
+            let mut clause = Vec::new();
+            for num in splits {
+                match num.parse::<i32>() {
+                    Ok(0) => break,
+                    Ok(literal) => clause.push(literal),
+                    Err(_) => return Err(format!("{num} is not a number").into()),
+                }
+            }
+            match clause.first() {
+                Some(witness) => reconstruction.push(*witness, clause),
+                None => return Err("Reconstruction entry without a witness".into()),
+            }
+        }
+        match vars {
+            Some(vars) => Ok((reconstruction, vars)),
+            None => Err("Missing reconstruction header".into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Reconstruction;
+    use crate::assignment::Assignment;
+    use crate::solver_state::SolverMove;
+
+    #[test]
+    fn witnesses_repair_removed_clauses() {
+        // 3 was eliminated from (1 3) (2 -3), leaving the resolvent (1 2)
+        let mut reconstruction = Reconstruction::new();
+        reconstruction.push(3, vec![1, 3]);
+
+        let mut assignment = Assignment::from_movelist(&vec![SolverMove::Decide(-1), SolverMove::Decide(2)], 3);
+        reconstruction.extend(&mut assignment);
+        assert_eq!(assignment.literals(), vec![-1, 2, 3]);
+
+        let mut assignment = Assignment::from_movelist(&vec![SolverMove::Decide(1), SolverMove::Decide(-2)], 3);
+        reconstruction.extend(&mut assignment);
+        assert_eq!(assignment.literals(), vec![1, -2, -3]);
+    }
+
+    #[test]
+    fn write_and_read_back() {
+        let mut reconstruction = Reconstruction::new();
+        reconstruction.push(3, vec![1, 3]);
+        reconstruction.push(-2, vec![-2, 4, -5]);
+        let mut buf = Vec::new();
+        reconstruction.write(5, &mut buf).unwrap();
+        assert_eq!(String::from_utf8(buf.clone()).unwrap(), "p rec 5 2\n3 1 0\n-2 4 -5 0\n");
+
+        let (read, vars) = Reconstruction::read(&buf[..]).unwrap();
+        assert_eq!(vars, 5);
+        assert_eq!(read.entries(), &[(3, vec![3, 1]), (-2, vec![-2, 4, -5])]);
+        assert!(Reconstruction::read("3 1 0\n".as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,213 @@
+use std::time::Instant;
+
+use num_bigint::BigUint;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::local_search::SearchResult;
+use crate::maxsat::Optimum;
+use crate::solver_state::SolverState;
+
+/// Wall-clock time spent in each phase of a run, in seconds
+#[derive(Serialize, Default, Debug)]
+pub struct PhaseTimes {
+    pub parse: f64,
+    pub preprocess: f64,
+    pub search: f64,
+    pub reconstruction: f64,
+}
+
+impl PhaseTimes {
+    pub fn total(&self) -> f64 {
+        self.parse + self.preprocess + self.search + self.reconstruction
+    }
+}
+
+/// Builds the machine-readable summary of a finished run
+pub fn json_report(state: &SolverState, sat: bool, times: &mut PhaseTimes) -> Value {
+    let stats = state.stats();
+    let model = if sat {
+        // The model runs the reconstruction stack over the variables preprocessing removed
+        let reconstruction_time = Instant::now();
+        let model = state.model();
+        times.reconstruction += reconstruction_time.elapsed().as_secs_f64();
+        Some(model)
+    } else {
+        None
+    };
+    let objective = match (state.objective(), &model) {
+        (Some(objective), Some(model)) => Some(objective.value(model)),
+        _ => None,
+    };
+
+    json!({
+        "status": if sat { "SATISFIABLE" } else { "UNSATISFIABLE" },
+        "model": model.map(|model| model.literals()),
+        "objective": objective,
+        "variables": state.vars(),
+        "original_clauses": state.original_clause_count,
+        "statistics": {
+            "moves": stats.moves(),
+            "decisions": stats.decisions,
+            "propagations": stats.propagations,
+            "conflicts": stats.conflicts,
+            "restarts": stats.restarts,
+            "learned_clauses": stats.learned_clauses,
+            "deleted_clauses": stats.deleted_clauses,
+            "chrono_backtracks": stats.chrono_backtracks,
+            "avg_learned_length": stats.avg_learned_len(),
+            "avg_learned_lbd": stats.avg_lbd(),
+            "minimized_literals": stats.minimized_literals,
+            "max_decision_level": stats.max_decision_level,
+            "eliminated_variables": stats.eliminated_variables,
+            "failed_literals": stats.failed_literals,
+            "substituted_variables": stats.substituted_variables,
+            "blocked_clauses": stats.blocked_clauses,
+            "subsumed_clauses": stats.subsumed_clauses,
+            "strengthened_literals": stats.strengthened_literals,
+            "vivified_clauses": stats.vivified_clauses,
+            "vivified_literals": stats.vivified_literals,
+            "vivify_propagations": stats.vivify_propagations,
+            "flips": stats.flips,
+            "rephases": stats.rephases,
+            "components": stats.components,
+            "cache_hits": stats.cache_hits,
+            "xor_explanations": stats.xor_explanations,
+            "pb_explanations": stats.pb_explanations,
+            "cells": stats.cells,
+            "cores": stats.cores,
+            "rotations": stats.rotations,
+            "propagations_per_second": stats.propagations_per_second(),
+        },
+        "timing": {
+            "parse": times.parse,
+            "preprocess": times.preprocess,
+            "search": times.search,
+            "reconstruction": times.reconstruction,
+            "total": times.total(),
+        },
+        "heuristic": state.heuristic().name(),
+        "config": state.config(),
+    })
+}
+
+/// Builds the summary of a standalone local search run, with the best assignment found extended
+/// over the clauses removed by preprocessing as the model. The status is `UNKNOWN` unless that
+/// assignment satisfies every clause.
+pub fn json_sls_report(state: &SolverState, result: &SearchResult, times: &mut PhaseTimes) -> Value {
+    let mut model = result.assignment.clone();
+    let reconstruction_time = Instant::now();
+    state.reconstruction().extend(&mut model);
+    times.reconstruction += reconstruction_time.elapsed().as_secs_f64();
+    let mut report = json_report(state, result.unsatisfied == 0, times);
+    if result.unsatisfied > 0 {
+        report["status"] = json!("UNKNOWN");
+    }
+    report["model"] = json!(model.literals());
+    report["unsatisfied_clauses"] = json!(result.unsatisfied);
+    report
+}
+
+/// Builds the summary of a model enumeration, with the first model found as the model.
+/// `limit_reached` tells whether enumeration stopped at the requested number of models.
+pub fn json_models_report(state: &SolverState, models: &[Vec<i32>], limit_reached: bool, times: &mut PhaseTimes) -> Value {
+    let mut report = json_report(state, !models.is_empty(), times);
+    report["model"] = json!(models.first());
+    report["models"] = json!(models);
+    report["model_count"] = json!(models.len());
+    report["limit_reached"] = json!(limit_reached);
+    report
+}
+
+/// Builds the summary of a model count, exact or approximate. The count is a string, as it can
+/// exceed every JSON number type.
+pub fn json_count_report(state: &SolverState, count: &BigUint, approximate: bool, times: &mut PhaseTimes) -> Value {
+    let sat = *count != BigUint::from(0u8);
+    let mut report = json_report(state, sat, times);
+    report["model"] = Value::Null;
+    report["count"] = json!(count.to_string());
+    report["projected"] = json!(state.independent_support().is_some());
+    report["approximate"] = json!(approximate);
+    report
+}
+
+/// Builds the summary of a MaxSAT run, with the optimum restricted to the first `vars`
+/// variables, those of the input, as the model
+pub fn json_maxsat_report(state: &SolverState, optimum: Option<&Optimum>, vars: usize, times: &mut PhaseTimes) -> Value {
+    let mut report = json_report(state, optimum.is_some(), times);
+    match optimum {
+        Some(optimum) => {
+            let literals: Vec<i32> = optimum.model.literals().into_iter().filter(|literal| literal.unsigned_abs() as usize <= vars).collect();
+            report["status"] = json!("OPTIMUM FOUND");
+            report["model"] = json!(literals);
+            report["cost"] = json!(optimum.cost);
+            report["objective"] = json!(state.objective().map(|objective| objective.value(&optimum.model)));
+        }
+        None => report["cost"] = Value::Null,
+    }
+    report
+}
+
+/// Builds the summary of a MUS extraction, listing the clause or group numbers of the subset
+/// found when the formula is unsatisfiable
+pub fn json_mus_report(state: &SolverState, mus: Option<&[usize]>, times: &mut PhaseTimes) -> Value {
+    let mut report = json_report(state, mus.is_none(), times);
+    report["model"] = Value::Null;
+    report["mus"] = json!(mus);
+    report["groups"] = json!(state.groups().is_some());
+    report
+}
+
+/// Builds the summary of a minimal correction subset enumeration, listing the clause or group
+/// numbers of every subset found. The formula is satisfiable when the only one is empty.
+pub fn json_mcs_report(state: &SolverState, mcses: &[Vec<usize>], times: &mut PhaseTimes) -> Value {
+    let sat = mcses.first().is_some_and(|mcs| mcs.is_empty());
+    let mut report = json_report(state, sat, times);
+    report["model"] = Value::Null;
+    report["mcses"] = json!(mcses);
+    report["mcs_count"] = json!(mcses.len());
+    report["groups"] = json!(state.groups().is_some());
+    report
+}
+
+/// Report emitted in place of a result when the input could not be read
+pub fn json_error(message: &str) -> Value {
+    json!({
+        "status": "ERROR",
+        "error": message,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{json_report, PhaseTimes};
+    use crate::clause::Clause;
+    use crate::solver_state::{SolverMove, SolverState};
+
+    #[test]
+    fn sat_report_contains_model() {
+        let mut state = SolverState::new();
+        state.set_vars(2);
+        state.add_clause(Clause::from_vec(vec![1, -2]));
+        state.original_clause_count = 1;
+        state.add_decision_lv();
+        state.add_move(SolverMove::Decide(-2));
+        state.add_move(SolverMove::Propagate { variable: 1, clause: 0 });
+
+        let report = json_report(&state, true, &mut PhaseTimes::default());
+        assert_eq!(report["status"], "SATISFIABLE");
+        assert_eq!(report["model"], serde_json::json!([1, -2]));
+        assert_eq!(report["statistics"]["decisions"], 1);
+        assert_eq!(report["statistics"]["propagations"], 1);
+        assert_eq!(report["statistics"]["restarts"], 0);
+        assert_eq!(report["timing"]["total"], report["timing"]["reconstruction"]);
+    }
+
+    #[test]
+    fn unsat_report_has_no_model() {
+        let state = SolverState::new();
+        let report = json_report(&state, false, &mut PhaseTimes::default());
+        assert_eq!(report["status"], "UNSATISFIABLE");
+        assert!(report["model"].is_null());
+    }
+}
@@ -0,0 +1,184 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+
+use crate::assignment::Assignment;
+use crate::config::SolverConfig;
+use crate::parser::parse_input;
+use crate::preprocess::preprocess;
+use crate::reconstruction::Reconstruction;
+use crate::solver_state::SolverState;
+
+/// Writes the original clauses of a state in DIMACS CNF format, preceded by its independent
+/// support as a `c ind` line and followed by its XOR constraints as `x` lines and its
+/// cardinality constraints in MiniCard's `cnf+` format. Pseudo-Boolean constraints with other
+/// coefficients cannot be written.
+pub fn write_dimacs<W: Write>(state: &SolverState, out: &mut W) -> io::Result<()> {
+    let clauses = &state.clauselist()[..state.original_clause_count];
+    // Constraints that always hold are left out
+    let xors: Vec<_> = state.xors().iter().filter(|xor| !xor.vars.is_empty() || xor.parity).collect();
+    let pbs: Vec<_> = state.pbs().iter().filter(|pb| pb.bound > 0).collect();
+    if !pbs.iter().all(|pb| pb.is_cardinality()) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "pseudo-Boolean constraints with coefficients other than 1 have no DIMACS form",
+        ));
+    }
+    let format = if pbs.is_empty() { "cnf" } else { "cnf+" };
+    writeln!(out, "p {format} {} {}", state.vars(), clauses.len() + xors.len() + pbs.len())?;
+    if let Some(support) = state.independent_support() {
+        write!(out, "c ind ")?;
+        for var in support {
+            write!(out, "{var} ")?;
+        }
+        writeln!(out, "0")?;
+    }
+    for clause in clauses {
+        for literal in &clause.vars {
+            write!(out, "{literal} ")?;
+        }
+        writeln!(out, "0")?;
+    }
+    for xor in xors {
+        write!(out, "x")?;
+        for literal in xor.literals() {
+            write!(out, "{literal} ")?;
+        }
+        writeln!(out, "0")?;
+    }
+    for pb in pbs {
+        for (_, literal) in &pb.terms {
+            write!(out, "{literal} ")?;
+        }
+        writeln!(out, ">= {}", pb.bound)?;
+    }
+    Ok(())
+}
+
+/// Reads a model as printed by SAT solvers: literals on `v` lines or on lines of plain literals,
+/// such as the assignment printed by this solver. Other lines are skipped.
+pub fn read_model<R: BufRead>(reader: R, vars: usize) -> Result<Assignment, Box<dyn Error>> {
+    let mut literals = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        let values = match line.strip_prefix('v') {
+            Some(values) => values,
+            None if line.split_whitespace().next().is_some_and(|num| num.parse::<i32>().is_ok()) => &line,
+            None => continue,
+        };
+        for num in values.split_whitespace() {
+            match num.parse::<i32>() {
+                Ok(0) => (),
+                Ok(literal) if literal.unsigned_abs() as usize <= vars => literals.push(literal),
+                Ok(literal) => return Err(format!("Literal {literal} is beyond variable {vars}").into()),
+                Err(_) => return Err(format!("{num} is not a number").into()),
+            }
+        }
+    }
+    Ok(Assignment::from_literals(&literals, vars))
+}
+
+/// Runs level 0 propagation and the preprocessing passes enabled in `config` on `input`, then
+/// writes the simplified formula to `formula` and the reconstruction stack to `reconstruction`.
+/// Returns the state after preprocessing.
+pub fn simplify_file(
+    input: &str,
+    formula: &str,
+    reconstruction: &str,
+    config: SolverConfig,
+) -> Result<SolverState, Box<dyn Error>> {
+    let mut state = parse_input(input)?;
+    state.set_config(config);
+    preprocess(&mut state);
+
+    let mut out = BufWriter::new(File::create(formula)?);
+    write_dimacs(&state, &mut out)?;
+    out.flush()?;
+    let mut out = BufWriter::new(File::create(reconstruction)?);
+    state.reconstruction().write(state.vars(), &mut out)?;
+    out.flush()?;
+    Ok(state)
+}
+
+/// Extends the model in `model` of a simplified formula to the original formula using the
+/// reconstruction stack in `reconstruction`
+pub fn reconstruct_file(reconstruction: &str, model: &str) -> Result<Assignment, Box<dyn Error>> {
+    let (reconstruction, vars) = Reconstruction::read(BufReader::new(File::open(reconstruction)?))?;
+    let mut assignment = read_model(BufReader::new(File::open(model)?), vars)?;
+    reconstruction.extend(&mut assignment);
+    Ok(assignment)
+}
+
+#[cfg(test)]
+mod tests {
+    use num_bigint::BigUint;
+
+    use super::{read_model, write_dimacs};
+    use crate::count::count;
+    use crate::parser::parse_reader;
+    use crate::preprocess::preprocess;
+    use crate::reconstruction::Reconstruction;
+    use crate::solver::solve;
+
+    #[test]
+    fn simplified_formula_round_trips() {
+        let input = "p cnf 6 7\n1 4 0\n2 -4 0\n-1 -2 0\n1 3 0\n-3 5 6 0\n-5 -6 0\n2 6 0\n";
+        let mut state = parse_reader(input.as_bytes()).unwrap();
+        let mut config = state.config().clone();
+        config.substitute = true;
+        config.eliminate = true;
+        config.block = true;
+        state.set_config(config);
+        preprocess(&mut state);
+        let mut formula = Vec::new();
+        write_dimacs(&state, &mut formula).unwrap();
+        let mut stack = Vec::new();
+        state.reconstruction().write(state.vars(), &mut stack).unwrap();
+
+        // Solve the written formula separately, as another solver would
+        let mut simplified = parse_reader(&formula[..]).unwrap();
+        assert_eq!(simplified.vars(), 6);
+        assert!(simplified.clauses() < 7);
+        assert!(solve(&mut simplified));
+        let model = format!("s SATISFIABLE\nv {}0\n", simplified.model());
+
+        let (reconstruction, vars) = Reconstruction::read(&stack[..]).unwrap();
+        assert!(!reconstruction.is_empty());
+        let mut assignment = read_model(model.as_bytes(), vars).unwrap();
+        reconstruction.extend(&mut assignment);
+        let original = parse_reader(input.as_bytes()).unwrap();
+        for clause in original.clauselist() {
+            assert!(clause.vars.iter().any(|literal| assignment.value(*literal) == Some(true)));
+        }
+    }
+
+    #[test]
+    fn independent_support_is_kept() {
+        let input = "c ind 1 3 0\np cnf 3 2\n1 2 0\n-2 3 0\n";
+        let state = parse_reader(input.as_bytes()).unwrap();
+        let mut formula = Vec::new();
+        write_dimacs(&state, &mut formula).unwrap();
+        let written = parse_reader(&formula[..]).unwrap();
+        assert_eq!(written.independent_support(), Some(&[1, 3][..]));
+
+        // Eliminating a support variable or a clause would change the projected model count the
+        // written formula claims
+        let mut state = parse_reader(input.as_bytes()).unwrap();
+        let mut config = state.config().clone();
+        config.eliminate = true;
+        config.block = true;
+        state.set_config(config);
+        preprocess(&mut state);
+        let mut formula = Vec::new();
+        write_dimacs(&state, &mut formula).unwrap();
+        let mut written = parse_reader(&formula[..]).unwrap();
+        assert_eq!(count(&mut written), BigUint::from(3u8));
+    }
+
+    #[test]
+    fn models_are_read() {
+        assert_eq!(read_model("v 1 -2 0\n".as_bytes(), 2).unwrap().literals(), vec![1, -2]);
+        assert_eq!(read_model("Assignment:\n-1 2 \n".as_bytes(), 2).unwrap().literals(), vec![-1, 2]);
+        assert!(read_model("v 1 -3 0\n".as_bytes(), 2).is_err());
+    }
+}
@@ -0,0 +1,355 @@
+use crate::assignment::Assignment;
+use crate::clause::AssignmentResult;
+use crate::config::Algorithm;
+use crate::inprocess;
+use crate::local_search;
+use crate::solver_state::{Reason, SolverMove, SolverState};
+
+/// Runs the search to completion, returning whether the formula is satisfiable.
+/// With `Algorithm::Sls` a local search run sets the phases of a CDCL search, which follows
+/// them straight to the model if local search found one.
+pub fn solve(state: &mut SolverState) -> bool {
+    solve_with_assumptions(state, &[])
+}
+
+/// Runs the search with `assumptions` decided before any other literal, returning whether the
+/// formula has a model in which they all hold. When it has none, `SolverState::core` gives the
+/// assumptions responsible. Learned clauses stay between calls, so a formula can be solved
+/// again under other assumptions or with clauses and constraints added without starting over.
+pub fn solve_with_assumptions(state: &mut SolverState, assumptions: &[i32]) -> bool {
+    state.reset_trail();
+    state.set_core(Vec::new());
+    if state.config().algorithm == Algorithm::Sls {
+        let result = local_search::search(state);
+        state.set_phases(&result.assignment);
+    }
+    loop {
+        let next_move = move_from_state(state);
+        match next_move {
+            SolverMove::Propagate { variable, clause } => state.add_move(SolverMove::Propagate {
+                variable,
+                clause,
+            }),
+            SolverMove::PropagateConstraint { variable, reason } => {
+                state.add_move(SolverMove::PropagateConstraint { variable, reason })
+            }
+            SolverMove::Decide(var) => {
+                let literal = match next_assumption(state, assumptions) {
+                    Ok(Some(assumption)) => assumption,
+                    Ok(None) => var,
+                    Err(core) => {
+                        state.set_core(core);
+                        return false;
+                    }
+                };
+                state.add_decision_lv();
+                state.add_move(SolverMove::Decide(literal))
+            }
+            // A model leaving assumptions open extends to one with them true
+            SolverMove::Sat() => match next_assumption(state, assumptions) {
+                Ok(Some(assumption)) => {
+                    state.add_decision_lv();
+                    state.add_move(SolverMove::Decide(assumption))
+                }
+                Ok(None) => return true,
+                Err(core) => {
+                    state.set_core(core);
+                    return false;
+                }
+            },
+            SolverMove::DecideFromConflict(_, _) => panic!("Next move cannot by DecideFromConflict"),
+            SolverMove::Conflict(_) | SolverMove::ConstraintConflict(_) => {
+                let conflict = state.conflict_clause(&next_move);
+                let resolved = match state.config().algorithm {
+                    Algorithm::Cdcl | Algorithm::Sls => state.resolve_conflict_cdcl(conflict),
+                    Algorithm::Dpll => state.resolve_conflict_dpll(conflict),
+                };
+                if !resolved {
+                    return false;
+                }
+                let conflicts = state.stats().conflicts;
+                let learning = state.config().algorithm != Algorithm::Dpll;
+                let subsume_interval = state.config().subsume_interval;
+                if learning && subsume_interval != 0 && conflicts.is_multiple_of(subsume_interval) {
+                    inprocess::subsume_learned(state);
+                }
+                let vivify_interval = state.config().vivify_interval;
+                if learning && vivify_interval != 0 && conflicts.is_multiple_of(vivify_interval) {
+                    inprocess::vivify(state);
+                }
+                let rephase_interval = state.config().rephase_interval;
+                if learning && rephase_interval != 0 && conflicts.is_multiple_of(rephase_interval) {
+                    local_search::rephase(state);
+                }
+                let interval = state.config().report_interval;
+                if interval != 0 && state.stats().conflicts.is_multiple_of(interval) {
+                    println!("{}", state.stats_mut().progress_report());
+                }
+            }
+        }
+    }
+}
+
+// First assumption still unassigned, or the core behind the first one that is false
+fn next_assumption(state: &SolverState, assumptions: &[i32]) -> Result<Option<i32>, Vec<i32>> {
+    if assumptions.is_empty() {
+        return Ok(None);
+    }
+    let assignment = Assignment::from_movelist(&state.get_movelist(), state.vars());
+    for assumption in assumptions {
+        match assignment.value(*assumption) {
+            Some(true) => continue,
+            Some(false) => return Err(state.analyze_final(*assumption, assumptions)),
+            None => return Ok(Some(*assumption)),
+        }
+    }
+    Ok(None)
+}
+
+pub fn move_from_state(state: &mut SolverState) -> SolverMove {
+    let assignment = Assignment::from_movelist(&state.get_movelist(), state.vars());
+    let clause_status = match propagation_move(state, &assignment) {
+        Ok(clause_status) => clause_status,
+        Err(next_move) => return next_move,
+    };
+
+    if clause_status.iter().all(|sat| *sat) {
+        // The heuristics only branch on unsatisfied clauses, so an XOR constraint that is still
+        // open gets a decision on one of its variables, and a pseudo-Boolean constraint one on a
+        // literal it needs
+        if let Some(var) = state.xors().iter().find_map(|xor| xor.unassigned(&assignment)) {
+            return match state.phase(var) {
+                Some(true) => SolverMove::Decide(var as i32),
+                _ => SolverMove::Decide(-(var as i32)),
+            };
+        }
+        return match state.pbs().iter().find_map(|pb| pb.open_literal(&assignment)) {
+            Some(literal) => SolverMove::Decide(literal),
+            None => SolverMove::Sat(),
+        };
+    }
+
+    let var = state.pick_branch_literal(&assignment, &clause_status);
+    SolverMove::Decide(var)
+}
+
+/// Next propagation or conflict under the trail, None once propagation is done
+pub fn propagation_from_state(state: &mut SolverState) -> Option<SolverMove> {
+    let assignment = Assignment::from_movelist(&state.get_movelist(), state.vars());
+    propagation_move(state, &assignment).err()
+}
+
+// Propagation or conflict under `assignment`, or else which clauses it satisfies
+fn propagation_move(state: &mut SolverState, assignment: &Assignment) -> Result<Vec<bool>, SolverMove> {
+    let mut clause_status = vec![false; state.clauses()]; // True indicates the clause is sat
+
+    // Loop through clauses and check for possible propagates or conflicts
+    for (clause_index, clause) in state.clauselist().iter().enumerate() {
+        let clause_result = clause.check_assignment(assignment);
+
+        match clause_result {
+            Some(status) => match status {
+                AssignmentResult::Propagate(var) => {
+                    return Err(SolverMove::Propagate {
+                        variable: var,
+                        clause: clause_index,
+                    })
+                }
+                AssignmentResult::Conflict() => return Err(SolverMove::Conflict(clause_index)),
+                AssignmentResult::Sat() => clause_status[clause_index] = true,
+            },
+            None => continue,
+        }
+    }
+
+    // XOR and pseudo-Boolean constraints record themselves as the reason, explained only if
+    // conflict analysis comes to the literals they imply
+    if let Some((implied, row)) = state.propagate_xors() {
+        return Err(match implied {
+            Some(variable) => SolverMove::PropagateConstraint {
+                variable,
+                reason: Reason::Xor(row),
+            },
+            None => SolverMove::ConstraintConflict(Reason::Xor(row)),
+        });
+    }
+    if let Some((implied, index)) = state.propagate_pbs(assignment) {
+        return Err(match implied {
+            Some(variable) => SolverMove::PropagateConstraint {
+                variable,
+                reason: Reason::Pb(index),
+            },
+            None => SolverMove::ConstraintConflict(Reason::Pb(index)),
+        });
+    }
+    Ok(clause_status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{solve, solve_with_assumptions};
+    use crate::assignment::Assignment;
+    use crate::clause::AssignmentResult;
+    use crate::config::{Algorithm, Decision, SolverConfig};
+    use crate::parser::parse_reader;
+    use crate::testutil::random_3sat;
+
+    #[test]
+    fn same_seed_same_trace() {
+        let input = random_3sat(30, 130, 1);
+        let mut traces = Vec::new();
+        for _ in 0..2 {
+            let mut state = parse_reader(input.as_bytes()).unwrap();
+            state.set_seed(42);
+            let sat = solve(&mut state);
+            let moves = state.stats().moves();
+            let learned: Vec<Vec<i32>> = state.clauselist().iter().map(|c| c.vars.clone()).collect();
+            traces.push((sat, moves, format!("{:?}", state.get_movelist()), learned));
+        }
+        assert_eq!(traces[0], traces[1]);
+    }
+
+    #[test]
+    fn different_seeds_different_traces() {
+        let input = random_3sat(30, 130, 1);
+        let mut traces = Vec::new();
+        for seed in [1, 2] {
+            let mut state = parse_reader(input.as_bytes()).unwrap();
+            let mut config = state.config().clone();
+            config.decision = Decision::Random;
+            config.seed = seed;
+            state.set_config(config);
+            solve(&mut state);
+            traces.push(format!("{:?}", state.get_movelist()));
+        }
+        assert_ne!(traces[0], traces[1]);
+    }
+
+    #[test]
+    fn statistics_track_search() {
+        let input = random_3sat(30, 150, 3);
+        let mut state = parse_reader(input.as_bytes()).unwrap();
+        let sat = solve(&mut state);
+        let stats = state.stats();
+        assert!(!sat);
+        assert!(stats.conflicts > 0);
+        // The final conflict proves unsatisfiability and is not learned
+        assert_eq!(stats.learned_clauses + 1, stats.conflicts);
+        assert!(stats.avg_lbd() <= stats.avg_learned_len());
+        assert!(stats.max_decision_level <= state.vars());
+        assert_eq!(stats.moves(), stats.decisions + stats.propagations);
+    }
+
+    #[test]
+    fn configurations_agree() {
+        let decisions = [
+            Decision::FirstUnsat,
+            Decision::Bohm,
+            Decision::Activity,
+            Decision::Random,
+            Decision::Vmtf,
+            Decision::Lrb,
+            Decision::Chb,
+            Decision::Jw,
+            Decision::JwTwoSided,
+            Decision::Moms,
+            Decision::Dlis,
+            Decision::Dlcs,
+        ];
+        for instance in 0..8 {
+            let input = random_3sat(25, 110, instance);
+            let mut results = Vec::new();
+            for algorithm in [Algorithm::Cdcl, Algorithm::Dpll, Algorithm::Sls] {
+                for decision in decisions {
+                    let mut config = SolverConfig::new();
+                    config.algorithm = algorithm;
+                    config.decision = decision;
+                    config.seed = instance;
+                    config.sls_flips = 1000;
+                    let mut state = parse_reader(input.as_bytes()).unwrap();
+                    state.set_config(config);
+                    results.push(solve(&mut state));
+                }
+            }
+            assert!(results.iter().all(|sat| *sat == results[0]), "instance {instance}: {results:?}");
+        }
+    }
+
+    #[test]
+    fn chronological_backtracking_finds_models() {
+        let mut chrono_backtracks = 0;
+        for instance in 0..8 {
+            let input = random_3sat(40, 170, instance);
+            let mut results = Vec::new();
+            for threshold in [0, 100] {
+                let mut config = SolverConfig::new();
+                config.chrono_threshold = threshold;
+                let mut state = parse_reader(input.as_bytes()).unwrap();
+                state.set_config(config);
+                let sat = solve(&mut state);
+                if sat {
+                    let assignment = Assignment::from_movelist(&state.get_movelist(), state.vars());
+                    for clause in &state.clauselist()[..state.original_clause_count] {
+                        assert_eq!(clause.check_assignment(&assignment), Some(AssignmentResult::Sat()));
+                    }
+                }
+                if threshold == 0 {
+                    chrono_backtracks += state.stats().chrono_backtracks;
+                }
+                results.push(sat);
+            }
+            assert_eq!(results[0], results[1], "instance {instance}");
+        }
+        assert!(chrono_backtracks > 0);
+    }
+
+    #[test]
+    fn inprocessing_during_search_agrees() {
+        let mut simplified = 0;
+        // Inprocessing restarts the search, which never restarts otherwise
+        let mut restarts = [0, 0];
+        for instance in 0..8 {
+            let input = random_3sat(40, 170, instance);
+            let mut results = Vec::new();
+            for (run, interval) in [0, 2].into_iter().enumerate() {
+                let mut config = SolverConfig::new();
+                config.subsume_interval = interval;
+                config.vivify_interval = interval;
+                config.vivify_effort = 1.0;
+                let mut state = parse_reader(input.as_bytes()).unwrap();
+                state.set_config(config);
+                results.push(solve(&mut state));
+                let stats = state.stats();
+                simplified += stats.subsumed_clauses + stats.strengthened_literals + stats.vivified_clauses;
+                restarts[run] += stats.restarts;
+            }
+            assert_eq!(results[0], results[1], "instance {instance}");
+        }
+        assert!(simplified > 0);
+        assert_eq!(restarts[0], 0);
+        assert!(restarts[1] > 0);
+    }
+
+    #[test]
+    fn assumptions_give_cores() {
+        // 1 implies 3 through 2, which rules out 4
+        let input = "p cnf 5 3\n-1 2 0\n-2 3 0\n-3 -4 0\n";
+        let mut state = parse_reader(input.as_bytes()).unwrap();
+        assert!(solve_with_assumptions(&mut state, &[1, 5]));
+        let model = state.model();
+        assert_eq!((model.value(1), model.value(3), model.value(5)), (Some(true), Some(true), Some(true)));
+
+        assert!(!solve_with_assumptions(&mut state, &[5, 1, 4]));
+        let mut core = state.core().to_vec();
+        core.sort();
+        assert_eq!(core, vec![1, 4]);
+
+        // The formula itself is still satisfiable
+        assert!(solve(&mut state));
+        assert!(state.core().is_empty());
+
+        let mut state = parse_reader("p cnf 2 2\n1 0\n-1 0\n".as_bytes()).unwrap();
+        assert!(!solve_with_assumptions(&mut state, &[2]));
+        assert!(state.core().is_empty());
+    }
+}
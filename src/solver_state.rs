@@ -1,21 +1,86 @@
-use crate::{assignment::Assignment, clause::Clause};
-use std::collections::{BinaryHeap, HashMap, HashSet};
+use crate::decision::{self, DecisionContext, DecisionHeuristic};
+use crate::pb::{Objective, Pb, PbSlacks};
+use crate::reconstruction::Reconstruction;
+use crate::xor::{Xor, XorMatrix};
+use crate::config::{Algorithm, SolverConfig};
+use crate::{assignment::Assignment, clause::Clause, statistics::Statistics};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap};
+use std::fmt;
+
+/// Seed used when none is given, so that runs are reproducible by default
+pub const DEFAULT_SEED: u64 = 0;
 
 #[derive(Clone, Debug)]
 pub enum SolverMove {
     Propagate { variable: i32, clause: usize },
+    PropagateConstraint { variable: i32, reason: Reason },
     Decide(i32),
     DecideFromConflict(i32, usize),
     Sat(),
     Conflict(usize),
+    ConstraintConflict(Reason),
+}
+
+/// Constraint other than a clause that implied a literal or was violated. The clause explaining
+/// it is only built once conflict analysis asks for it.
+#[derive(Clone, Debug)]
+pub enum Reason {
+    /// Sum of XOR constraints
+    Xor(Xor),
+    /// Index of a cardinality or pseudo-Boolean constraint
+    Pb(usize),
+}
+
+impl SolverMove {
+    /// The literal this move puts on the trail, if any
+    pub fn literal(&self) -> Option<i32> {
+        match self {
+            SolverMove::Propagate { variable, clause: _ } => Some(*variable),
+            SolverMove::PropagateConstraint { variable, reason: _ } => Some(*variable),
+            SolverMove::Decide(variable) => Some(*variable),
+            SolverMove::DecideFromConflict(variable, _) => Some(*variable),
+            SolverMove::Sat() | SolverMove::Conflict(_) | SolverMove::ConstraintConflict(_) => None,
+        }
+    }
 }
 
 pub struct SolverState {
     clauselist: Vec<Clause>,
     movelist: Vec<Vec<SolverMove>>,
-    activitylist: Vec<f32>,
+    // Decision level of each assigned variable. After chronological backtracking this can be
+    // lower than the level the variable sits at on the trail.
+    levels: Vec<usize>,
+    // Polarity decisions take for each variable once local search has suggested one
+    phases: Vec<Option<bool>>,
+    heuristic: Box<dyn DecisionHeuristic>,
     vars: usize,
     pub original_clause_count: usize,
+    // XOR constraints of the formula
+    xors: Vec<Xor>,
+    // Rows of `xors` under the trail, built at the first propagation after a constraint is added
+    xor_matrix: Option<XorMatrix>,
+    // Cardinality and pseudo-Boolean constraints of the formula
+    pbs: Vec<Pb>,
+    // Slacks of `pbs` under the trail, built at the first propagation after a constraint is added
+    pb_slacks: Option<PbSlacks>,
+    // Function to minimize, if the formula came with one
+    objective: Option<Objective>,
+    // Weighted clauses a MaxSAT formula prefers to satisfy, next to the hard clauses
+    soft_clauses: Vec<(u64, Vec<i32>)>,
+    // Assumptions the last search under assumptions found unsatisfiable together
+    core: Vec<i32>,
+    // Group of each original clause in a GCNF formula, 0 for the clauses that are always kept
+    groups: Option<Vec<usize>>,
+    // Clauses removed by preprocessing, needed to extend models to the original formula
+    reconstruction: Reconstruction,
+    // Variables from `c ind` lines that models are projected onto, if any were given
+    independent_support: Option<Vec<usize>>,
+    // All randomness in the solver must be drawn from here so that a seed reproduces a run
+    rng: StdRng,
+    config: SolverConfig,
+    stats: Statistics,
 }
 
 impl SolverState {
@@ -23,17 +88,460 @@ impl SolverState {
         SolverState {
             clauselist: Vec::new(),
             movelist: Vec::new(),
+            levels: vec![0],
+            phases: vec![None],
             vars: 0,
-            activitylist: Vec::new(),
+            heuristic: decision::from_config(&SolverConfig::new()),
             original_clause_count: 0,
+            xors: Vec::new(),
+            xor_matrix: None,
+            pbs: Vec::new(),
+            pb_slacks: None,
+            objective: None,
+            soft_clauses: Vec::new(),
+            core: Vec::new(),
+            groups: None,
+            reconstruction: Reconstruction::new(),
+            independent_support: None,
+            rng: StdRng::seed_from_u64(DEFAULT_SEED),
+            config: SolverConfig::new(),
+            stats: Statistics::new(),
+        }
+    }
+
+    pub fn stats(&self) -> &Statistics {
+        &self.stats
+    }
+
+    pub fn stats_mut(&mut self) -> &mut Statistics {
+        &mut self.stats
+    }
+
+    pub fn set_seed(&mut self, seed: u64) {
+        self.config.seed = seed;
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.config.seed
+    }
+
+    /// Replaces the configuration, reseeding the random number generator and rebuilding the
+    /// decision heuristic from it
+    pub fn set_config(&mut self, config: SolverConfig) {
+        self.rng = StdRng::seed_from_u64(config.seed);
+        let heuristic = decision::from_config(&config);
+        self.config = config;
+        self.xor_matrix = None;
+        self.set_heuristic(heuristic);
+    }
+
+    /// Swaps in a different decision heuristic, replaying the current trail to it
+    pub fn set_heuristic(&mut self, mut heuristic: Box<dyn DecisionHeuristic>) {
+        heuristic.init(self.vars);
+        for (var, phase) in self.phases.iter().enumerate() {
+            if let Some(value) = phase {
+                heuristic.save_phase(var, *value);
+            }
+        }
+        for level in &self.movelist {
+            for solver_move in level {
+                if let Some(literal) = solver_move.literal() {
+                    heuristic.on_assign(literal);
+                }
+            }
+        }
+        self.heuristic = heuristic;
+    }
+
+    pub fn heuristic(&self) -> &dyn DecisionHeuristic {
+        self.heuristic.as_ref()
+    }
+
+    /// Asks the heuristic for a decision literal. Under `--algorithm=sls` its polarity is
+    /// overridden by the phase local search found for the variable, if there is one.
+    pub fn pick_branch_literal(&mut self, assignment: &Assignment, clause_status: &[bool]) -> i32 {
+        let mut ctx = DecisionContext {
+            assignment,
+            clauses: &self.clauselist,
+            clause_status,
+            rng: &mut self.rng,
+        };
+        let literal = self.heuristic.pick_branch_literal(&mut ctx);
+        if self.config.algorithm != Algorithm::Sls {
+            return literal;
+        }
+        match self.phase(literal.unsigned_abs() as usize) {
+            Some(true) => literal.abs(),
+            Some(false) => -literal.abs(),
+            None => literal,
+        }
+    }
+
+    pub fn phase(&self, var: usize) -> Option<bool> {
+        self.phases.get(var).copied().flatten()
+    }
+
+    /// Records the variables assigned in `phases` as the polarities to decide them on. A
+    /// heuristic doing phase saving takes them as its saved phases, which later assignments
+    /// overwrite as usual, and under `--algorithm=sls` every decision follows them.
+    pub fn set_phases(&mut self, phases: &Assignment) {
+        for var in 1..=self.vars.min(phases.len()) {
+            self.phases[var] = phases[var];
+            if let Some(value) = phases[var] {
+                self.heuristic.save_phase(var, value);
+            }
+        }
+    }
+
+    pub(crate) fn rng(&mut self) -> &mut StdRng {
+        &mut self.rng
+    }
+
+    /// The original clauses and the configuration together with the random number generator,
+    /// for searches that draw from it while reading the formula
+    pub(crate) fn original_clauses_with_rng(&mut self) -> (&[Clause], &SolverConfig, &mut StdRng) {
+        (&self.clauselist[..self.original_clause_count], &self.config, &mut self.rng)
+    }
+
+    pub fn config(&self) -> &SolverConfig {
+        &self.config
+    }
+
+    /// Replaces the original clauses of a state that has not started searching
+    pub fn replace_clauses(&mut self, clauses: Vec<Clause>) {
+        assert!(self.movelist.is_empty(), "Clauses replaced during search");
+        self.original_clause_count = clauses.len();
+        self.clauselist = clauses;
+    }
+
+    pub fn reconstruction(&self) -> &Reconstruction {
+        &self.reconstruction
+    }
+
+    pub fn set_reconstruction(&mut self, reconstruction: Reconstruction) {
+        self.reconstruction = reconstruction;
+    }
+
+    pub fn independent_support(&self) -> Option<&[usize]> {
+        self.independent_support.as_deref()
+    }
+
+    /// Adds variables to the set models are projected onto
+    pub fn add_independent_support(&mut self, vars: &[usize]) {
+        let support = self.independent_support.get_or_insert_with(Vec::new);
+        for var in vars {
+            if !support.contains(var) {
+                support.push(*var);
+            }
         }
+        support.sort();
+    }
+
+    /// The current assignment extended over the clauses removed by preprocessing, with the
+    /// variables of the objective it leaves open set to lower it
+    pub fn model(&self) -> Assignment {
+        let mut assignment = Assignment::from_movelist(&self.get_movelist(), self.vars());
+        self.reconstruction.extend(&mut assignment);
+        if let Some(objective) = &self.objective {
+            objective.complete(&mut assignment);
+        }
+        assignment
     }
 
     pub fn add_clause(&mut self, clause: Clause) {
         self.clauselist.push(clause);
     }
 
+    /// Adds a clause to the formula between searches, undoing the whole trail first. Learned
+    /// clauses are kept, as they still follow from the formula.
+    pub fn add_original_clause(&mut self, clause: Clause) {
+        self.backtrack(0);
+        self.clauselist.insert(self.original_clause_count, clause);
+        self.original_clause_count += 1;
+    }
+
+    /// Adds an XOR constraint to the formula, undoing the whole trail first
+    pub fn add_xor(&mut self, xor: Xor) {
+        self.backtrack(0);
+        self.xors.push(xor);
+        self.xor_matrix = None;
+    }
+
+    pub fn xors(&self) -> &[Xor] {
+        &self.xors
+    }
+
+    /// Sum of XOR constraints that is violated under the trail, or that implies the value of its
+    /// only unassigned variable, with the literal it implies. The constraints are summed by
+    /// Gauss-Jordan elimination under `--gauss`, and taken one at a time otherwise.
+    pub(crate) fn propagate_xors(&mut self) -> Option<(Option<i32>, Xor)> {
+        if self.xors.is_empty() {
+            return None;
+        }
+        let matrix = self.xor_matrix.get_or_insert_with(|| {
+            let mut matrix = XorMatrix::new(&self.xors, self.vars, self.config.gauss);
+            for solver_move in self.movelist.iter().flatten() {
+                if let Some(literal) = solver_move.literal() {
+                    matrix.assign(literal);
+                }
+            }
+            matrix
+        });
+        matrix.propagate()
+    }
+
+    /// Adds a cardinality or pseudo-Boolean constraint to the formula, undoing the whole trail
+    /// first
+    pub fn add_pb(&mut self, pb: Pb) {
+        self.backtrack(0);
+        self.pbs.push(pb);
+        self.pb_slacks = None;
+    }
+
+    pub fn pbs(&self) -> &[Pb] {
+        &self.pbs
+    }
+
+    /// Pseudo-Boolean constraint that is violated under `assignment`, the current one, or that
+    /// implies one of its unassigned literals, with that literal
+    pub(crate) fn propagate_pbs(&mut self, assignment: &Assignment) -> Option<(Option<i32>, usize)> {
+        if self.pbs.is_empty() {
+            return None;
+        }
+        let slacks = self.pb_slacks.get_or_insert_with(|| {
+            let mut slacks = PbSlacks::new(&self.pbs);
+            for solver_move in self.movelist.iter().flatten() {
+                if let Some(literal) = solver_move.literal() {
+                    slacks.assign(literal);
+                }
+            }
+            slacks
+        });
+        slacks.propagate(&self.pbs, assignment)
+    }
+
+    pub fn objective(&self) -> Option<&Objective> {
+        self.objective.as_ref()
+    }
+
+    pub fn set_objective(&mut self, objective: Objective) {
+        self.objective = Some(objective);
+    }
+
+    pub fn soft_clauses(&self) -> &[(u64, Vec<i32>)] {
+        &self.soft_clauses
+    }
+
+    pub fn add_soft_clause(&mut self, weight: u64, clause: Vec<i32>) {
+        self.soft_clauses.push((weight, clause));
+    }
+
+    /// Assumptions that the last call to `solve_with_assumptions` found to have no model
+    /// together, empty if the formula has none at all
+    pub fn core(&self) -> &[i32] {
+        &self.core
+    }
+
+    pub(crate) fn set_core(&mut self, core: Vec<i32>) {
+        self.core = core;
+    }
+
+    /// Group of each original clause in order, if the formula was read as GCNF
+    pub fn groups(&self) -> Option<&[usize]> {
+        self.groups.as_deref()
+    }
+
+    pub fn set_groups(&mut self, groups: Vec<usize>) {
+        self.groups = Some(groups);
+    }
+
+    /// Adds `count` fresh variables between searches, returning the first. The whole trail is
+    /// undone, while the decision heuristic keeps what it learned about the other variables.
+    pub fn add_vars(&mut self, count: usize) -> usize {
+        self.backtrack(0);
+        let first = self.vars + 1;
+        self.vars += count;
+        self.levels.resize(self.vars + 1, 0);
+        self.phases.resize(self.vars + 1, None);
+        self.heuristic.grow(self.vars);
+        self.xor_matrix = None;
+        first
+    }
+
+    /// Undoes the whole trail, ready for a new search
+    pub(crate) fn reset_trail(&mut self) {
+        self.backtrack(0);
+    }
+
+    /// Undoes the whole trail in the middle of a search, counted as a restart
+    pub(crate) fn restart(&mut self) {
+        self.backtrack(0);
+        self.stats.restarts += 1;
+    }
+
+    /// Assumptions that make `literal`, an assumption found false, false: those among the
+    /// decisions its reasons lead back to, together with `literal` itself. Flipped DPLL
+    /// decisions carry no reason, so under DPLL every assumption is taken.
+    pub(crate) fn analyze_final(&self, literal: i32, assumptions: &[i32]) -> Vec<i32> {
+        if self.config.algorithm == Algorithm::Dpll {
+            return assumptions.to_vec();
+        }
+        let trail = self.trail_index();
+        let mut core = vec![literal];
+        let mut seen = BTreeSet::from([literal.unsigned_abs() as usize]);
+        let mut stack = vec![literal.unsigned_abs() as usize];
+        while let Some(var) = stack.pop() {
+            if let Some(clause) = self.reason(var, &trail) {
+                for other in &clause {
+                    if seen.insert(other.unsigned_abs() as usize) {
+                        stack.push(other.unsigned_abs() as usize);
+                    }
+                }
+            } else if let Some(SolverMove::Decide(decided)) = self.trail_move(var, &trail) {
+                if assumptions.contains(decided) {
+                    core.push(*decided);
+                }
+            }
+        }
+        core
+    }
+
+    // Position on the trail, level and index within the level of each assigned variable
+    fn trail_index(&self) -> HashMap<usize, (usize, usize, usize)> {
+        let mut trail = HashMap::new();
+        let mut position = 0;
+        for (level, moves) in self.movelist.iter().enumerate() {
+            for (index, solver_move) in moves.iter().enumerate() {
+                if let Some(literal) = solver_move.literal() {
+                    trail.insert(literal.unsigned_abs() as usize, (position, level, index));
+                }
+                position += 1;
+            }
+        }
+        trail
+    }
+
+    fn trail_move(&self, var: usize, trail: &HashMap<usize, (usize, usize, usize)>) -> Option<&SolverMove> {
+        trail.get(&var).map(|(_, level, index)| &self.movelist[*level][*index])
+    }
+
+    // Clause that implied the value of `var` on the trail, None for decisions. Constraints
+    // other than clauses are explained by the literals that were false before it.
+    fn reason(&self, var: usize, trail: &HashMap<usize, (usize, usize, usize)>) -> Option<Vec<i32>> {
+        match self.trail_move(var, trail)? {
+            SolverMove::Propagate { variable: _, clause } | SolverMove::DecideFromConflict(_, clause) => {
+                Some(self.clauselist[*clause].vars.clone())
+            }
+            SolverMove::PropagateConstraint { variable, reason } => {
+                Some(self.explain(reason, Some(*variable), trail[&var].0, trail))
+            }
+            _ => None,
+        }
+    }
+
+    // `reason` built at most once per conflict analysis, counting the constraints explained
+    fn analysis_reason(
+        &mut self,
+        var: usize,
+        trail: &HashMap<usize, (usize, usize, usize)>,
+        reasons: &mut HashMap<usize, Option<Vec<i32>>>,
+    ) -> Option<Vec<i32>> {
+        if let Some(reason) = reasons.get(&var) {
+            return reason.clone();
+        }
+        let reason = self.reason(var, trail);
+        if let Some(SolverMove::PropagateConstraint { variable: _, reason }) = self.trail_move(var, trail) {
+            let reason = reason.clone();
+            self.count_explanation(&reason);
+        }
+        reasons.insert(var, reason.clone());
+        reason
+    }
+
+    fn count_explanation(&mut self, reason: &Reason) {
+        match reason {
+            Reason::Xor(_) => self.stats.xor_explanations += 1,
+            Reason::Pb(_) => self.stats.pb_explanations += 1,
+        }
+    }
+
+    // Clause of the constraint behind `reason` explaining why it implies `implied`, or why it
+    // is violated when that is None, from the literals false before `position` on the trail
+    fn explain(
+        &self,
+        reason: &Reason,
+        implied: Option<i32>,
+        position: usize,
+        trail: &HashMap<usize, (usize, usize, usize)>,
+    ) -> Vec<i32> {
+        let is_false = |literal: i32| match trail.get(&(literal.unsigned_abs() as usize)) {
+            Some((other, level, index)) => {
+                *other < position && self.movelist[*level][*index].literal() == Some(-literal)
+            }
+            None => false,
+        };
+        match reason {
+            Reason::Xor(xor) => xor.explain(implied, is_false),
+            Reason::Pb(index) => self.pbs[*index].explain(implied, is_false),
+        }
+    }
+
+    /// Literals of the clause falsified by `conflict`, a `Conflict` or `ConstraintConflict` move
+    pub fn conflict_clause(&mut self, conflict: &SolverMove) -> Vec<i32> {
+        match conflict {
+            SolverMove::Conflict(index) => self.clauselist[*index].vars.clone(),
+            SolverMove::ConstraintConflict(reason) => {
+                let clause = self.explain(reason, None, usize::MAX, &self.trail_index());
+                self.count_explanation(reason);
+                clause
+            }
+            _ => panic!("Not a conflict: {conflict:?}"),
+        }
+    }
+
+    /// Whether the formula consists of clauses only, which the passes and modes that do not know
+    /// the other constraint types require
+    pub fn is_clausal(&self) -> bool {
+        self.xors.is_empty() && self.pbs.is_empty()
+    }
+
     pub fn add_move(&mut self, item: SolverMove) {
+        let level = match &item {
+            // Implied at the highest level among the rest of its reason
+            SolverMove::Propagate { variable, clause } => self.clauselist[*clause]
+                .vars
+                .iter()
+                .filter(|other| other.unsigned_abs() != variable.unsigned_abs())
+                .map(|other| self.levels[other.unsigned_abs() as usize])
+                .max()
+                .unwrap_or(0),
+            _ => self.movelist.len().max(1) - 1,
+        };
+        self.add_move_at_level(item, level);
+    }
+
+    /// Puts a move on top of the trail while recording it as assigned at `level`, which is
+    /// below the top level for literals placed out of order
+    fn add_move_at_level(&mut self, item: SolverMove, level: usize) {
+        match item {
+            SolverMove::Decide(_) => self.stats.decisions += 1,
+            SolverMove::Propagate { .. } | SolverMove::PropagateConstraint { .. } | SolverMove::DecideFromConflict(_, _) => {
+                self.stats.propagations += 1
+            }
+            _ => (),
+        }
+        let is_decision = matches!(item, SolverMove::Decide(_));
+        if let Some(literal) = item.literal() {
+            self.levels[literal.unsigned_abs() as usize] = level;
+            self.heuristic.on_assign(literal);
+            if let Some(matrix) = &mut self.xor_matrix {
+                matrix.assign(literal);
+            }
+            if let Some(slacks) = &mut self.pb_slacks {
+                slacks.assign(literal);
+            }
+        }
         match self.movelist.last_mut() {
             Some(last_level) => last_level.push(item),
             None => {
@@ -41,64 +549,96 @@ impl SolverState {
                 self.movelist[0].push(item)
             }
         }
+        if is_decision {
+            self.stats.max_decision_level = self.stats.max_decision_level.max(self.decision_level());
+        }
+    }
+
+    /// Decision level `literal` was assigned at
+    pub fn level(&self, literal: i32) -> usize {
+        self.levels[literal.unsigned_abs() as usize]
+    }
+
+    /// Number of decisions on the current trail
+    pub fn decision_level(&self) -> usize {
+        match self.movelist.first().and_then(|level| level.first()) {
+            Some(SolverMove::Decide(_)) => self.movelist.len(),
+            Some(_) => self.movelist.len() - 1,
+            None => 0,
+        }
     }
 
     pub fn add_decision_lv(&mut self) {
         self.movelist.push(Vec::new());
     }
 
-    fn bump_activity(&mut self, var: i32) {
-        let var_index: usize = (TryInto::<usize>::try_into((var).abs()).unwrap()) - 1;
-        let index: usize = if var > 0 {
-            var_index
-        } else {
-            var_index + self.vars
-        };
-        let old = self.activitylist[index];
-        self.activitylist[index] = old + 1.0;
+    /// Number of levels on the trail, the one before the first decision included
+    pub(crate) fn trail_depth(&self) -> usize {
+        self.movelist.len()
     }
 
-    pub fn get_activity(&self, var: i32) -> f32 {
-        let var_index: usize = (TryInto::<usize>::try_into((var).abs()).unwrap()) - 1;
-        let index: usize = if var > 0 {
-            var_index
-        } else {
-            var_index + self.vars
-        };
-        return self.activitylist[index];
+    /// Removes every level from `levels` upwards from the trail. Literals placed out of order
+    /// that belong to a lower level are kept and moved to the new top of the trail.
+    pub(crate) fn backtrack(&mut self, levels: usize) {
+        let mut kept = Vec::new();
+        while self.movelist.len() > levels {
+            for solver_move in self.movelist.pop().unwrap().into_iter().rev() {
+                if let Some(literal) = solver_move.literal() {
+                    if self.level(literal) < levels {
+                        kept.push(solver_move);
+                    } else {
+                        self.heuristic.on_unassign(literal);
+                        if let Some(matrix) = &mut self.xor_matrix {
+                            matrix.unassign(literal);
+                        }
+                        if let Some(slacks) = &mut self.pb_slacks {
+                            slacks.unassign(literal);
+                        }
+                    }
+                }
+            }
+        }
+        if let Some(top) = self.movelist.last_mut() {
+            top.extend(kept.into_iter().rev());
+        }
     }
 
     pub fn clauselist(&self) -> &Vec<Clause> {
-        return &self.clauselist;
+        &self.clauselist
     }
 
     pub fn clauses(&self) -> usize {
-        return self.clauselist.len();
+        self.clauselist.len()
     }
 
-    fn find_deletable_clauses(&self) -> HashSet<usize> {
-        let mut all_set: HashSet<usize> = HashSet::new();
-        for i in self.original_clause_count..self.clauses() {
-            all_set.insert(i);
-        }
-
+    /// Clauses that are the reason for a literal on the trail
+    pub(crate) fn reason_clauses(&self) -> BTreeSet<usize> {
+        let mut reasons = BTreeSet::new();
         for level in &self.movelist {
             for solver_move in level {
                 match solver_move {
                     SolverMove::Propagate {
                         variable: _,
                         clause,
-                    } => all_set.remove(clause),
-                    SolverMove::DecideFromConflict(_, clause) => all_set.remove(clause),
+                    } => reasons.insert(*clause),
+                    SolverMove::DecideFromConflict(_, clause) => reasons.insert(*clause),
                     _ => continue,
                 };
             }
         }
+        reasons
+    }
 
-        return all_set;
+    /// Learned clauses that are not the reason for a literal on the trail
+    pub(crate) fn find_deletable_clauses(&self) -> BTreeSet<usize> {
+        let reasons = self.reason_clauses();
+        (self.original_clause_count..self.clauses())
+            .filter(|index| !reasons.contains(index))
+            .collect()
     }
 
-    fn forget_clause(&mut self, indexes: &HashSet<usize>) {
+    pub(crate) fn forget_clause(&mut self, indexes: &BTreeSet<usize>) {
+        self.stats.deleted_clauses += indexes.len();
         for index in indexes {
             assert!(*index > (self.original_clause_count - 1));
         }
@@ -163,6 +703,13 @@ impl SolverState {
             self.clauselist.remove(*index);
         }
     }
+
+    /// Removes `literal` from a clause that is implied without it. The clause must not be the
+    /// reason for a literal on the trail.
+    pub(crate) fn strengthen_clause(&mut self, index: usize, literal: i32) {
+        self.clauselist[index].vars.retain(|other| *other != literal);
+    }
+
     pub fn get_movelist(&self) -> Vec<SolverMove> {
         let mut moves: Vec<SolverMove> = Vec::new();
         for level in &self.movelist {
@@ -170,77 +717,59 @@ impl SolverState {
                 moves.push(item.clone());
             }
         }
-        return moves;
+        moves
     }
 
-    #[allow(dead_code)]
-    pub fn resolve_conflict_dpll(&mut self, clause_index: usize) -> bool {
+    /// Flips the last decision after a conflict, `conflict_clause` being the clause it falsified
+    pub fn resolve_conflict_dpll(&mut self, conflict_clause: Vec<i32>) -> bool {
         // DPLL Conflict Resolution:
+        self.stats.conflicts += 1;
 
         // Increase activity for all variables in conflict clause
-        let conflict_clause = self.clauselist[clause_index].vars.clone();
-        for var in &conflict_clause {
-            self.bump_activity(*var);
-        }
+        self.heuristic.on_analysis(&conflict_clause, &[]);
+        self.heuristic.on_conflict(&conflict_clause);
 
-        let last_decision = &self.movelist.last().unwrap()[0];
-        let var = match last_decision {
-            SolverMove::Decide(val) => *val,
+        let var = match self.movelist.last().and_then(|level| level.first()) {
+            Some(SolverMove::Decide(val)) => *val,
             _other => return false,
         };
 
-        self.movelist.remove(self.movelist.len() - 1);
-        self.add_move(SolverMove::DecideFromConflict(-1 * var, 0));
+        self.backtrack(self.movelist.len() - 1);
+        self.add_move(SolverMove::DecideFromConflict(-var, 0));
 
-        for i in 0..self.vars() * 2 {
-            self.activitylist[i] *= 0.95;
-        }
-
-        return true;
+        true
     }
 
-    #[allow(dead_code)]
-    pub fn resolve_conflict_cdcl(&mut self, clause_index: usize) -> bool {
-        if self.movelist.len() == 0 {
+    /// Learns a clause from the conflict falsifying `conflict_clause` and backjumps
+    pub fn resolve_conflict_cdcl(&mut self, conflict_clause: Vec<i32>) -> bool {
+        self.stats.conflicts += 1;
+        if self.movelist.is_empty() {
             // Nowhere to backjump to
             return false;
         }
 
         // Build structure for variable -> decision level
         let mut variable_level_map: HashMap<i32, usize> = HashMap::new();
-        for level in 0..self.movelist.len() {
-            for solver_move in &self.movelist[level] {
+        for level in &self.movelist {
+            for solver_move in level {
                 let var = match solver_move {
                     SolverMove::Propagate {
                         variable,
                         clause: _,
                     } => *variable,
+                    SolverMove::PropagateConstraint { variable, reason: _ } => *variable,
                     SolverMove::Decide(val) => *val,
                     SolverMove::DecideFromConflict(val, _) => *val,
                     SolverMove::Sat() => panic!("Sat found when resolving conflict"),
-                    SolverMove::Conflict(_) => panic!("Conflict in movelist"),
+                    SolverMove::Conflict(_) | SolverMove::ConstraintConflict(_) => panic!("Conflict in movelist"),
                 };
-                variable_level_map.insert(var, level);
+                variable_level_map.insert(var, self.level(var));
             }
         }
 
-        // Build structure for variable -> which clause explains it
-        let mut variable_clause_map: HashMap<i32, usize> = HashMap::new();
-        for level in 0..self.movelist.len() {
-            for solver_move in &self.movelist[level] {
-                match solver_move {
-                    SolverMove::Propagate { variable, clause } => {
-                        variable_clause_map.insert(*variable, *clause);
-                    }
-                    SolverMove::DecideFromConflict(variable, clause) => {
-                        variable_clause_map.insert(*variable, *clause);
-                    }
-                    SolverMove::Sat() => panic!("Sat found when resolving conflict"),
-                    SolverMove::Conflict(_) => panic!("Conflict in movelist"),
-                    _other => continue,
-                };
-            }
-        }
+        // Reasons of the variables on the trail, built as they are needed
+        let trail = self.trail_index();
+        let mut reasons: HashMap<usize, Option<Vec<i32>>> = HashMap::new();
 
         let last_decision = &self.movelist.last().unwrap()[0];
         let last_decided_var = match last_decision {
@@ -249,9 +778,9 @@ impl SolverState {
         };
 
         // Create conflict clause
-        let mut conflict_list: HashSet<i32> = HashSet::new();
-        let conflict_clause = &self.clauselist[clause_index];
-        for var in &conflict_clause.vars {
+        // Ordered so that the choice of resolving variable does not depend on hashing
+        let mut conflict_list: BTreeSet<i32> = BTreeSet::new();
+        for var in &conflict_clause {
             conflict_list.insert(*var);
         }
         conflict_list.remove(&last_decided_var);
@@ -259,9 +788,13 @@ impl SolverState {
         let current_level = self.movelist.len() - 1;
         //Begin conflict resolution
 
-        let mut active_vars = HashSet::new();
+        // Every literal seen while resolving, by variable
+        let mut conflict_side: BTreeMap<usize, i32> = BTreeMap::new();
+        for var in &conflict_list {
+            conflict_side.insert(var.unsigned_abs() as usize, *var);
+        }
         loop {
-            let mut clause: Option<&Clause> = None;
+            let mut clause: Option<Vec<i32>> = None;
             let mut var: Option<i32> = None;
 
             for conflict_var in &conflict_list {
@@ -274,8 +807,9 @@ impl SolverState {
                 // Check if the variable is in the current decision level
                 if *variable_level_map.get(&conflict_var_inv).unwrap() == current_level {
                     // Set Resolving clause
-                    let explaining_clause =
-                        &self.clauselist[*variable_clause_map.get(&conflict_var_inv).unwrap()]; //Maybe this should return if unwrap fails?
+                    let explaining_clause = self
+                        .analysis_reason(conflict_var.unsigned_abs() as usize, &trail, &mut reasons)
+                        .unwrap(); //Maybe this should return if unwrap fails?
                     clause = Some(explaining_clause);
                     var = Some(*conflict_var);
                     break;
@@ -288,58 +822,115 @@ impl SolverState {
             }
 
             // Resolve conflict and explaining clauses
-            for additional_var in &clause.unwrap().vars {
+            for additional_var in &clause.unwrap() {
                 conflict_list.insert(*additional_var);
-                active_vars.insert(*additional_var);
+                conflict_side.insert(additional_var.unsigned_abs() as usize, *additional_var);
             }
             conflict_list.remove(&var.unwrap());
-            conflict_list.remove(&(var.unwrap() * -1));
+            conflict_list.remove(&-var.unwrap());
         }
 
-
+        // Minimize: drop literals whose reason clause is otherwise contained in the conflict clause
+        let mut redundant = Vec::new();
+        for var in &conflict_list {
+            let var_inv = -var;
+            if var_inv == last_decided_var {
+                continue;
+            }
+            if let Some(reason) = self.analysis_reason(var.unsigned_abs() as usize, &trail, &mut reasons) {
+                let implied = reason
+                    .iter()
+                    .all(|other| *other == var_inv || conflict_list.contains(other));
+                if implied {
+                    redundant.push(*var);
+                }
+            }
+        }
+        for var in &redundant {
+            conflict_list.remove(var);
+        }
+        self.stats.minimized_literals += redundant.len();
 
         // Get levels of all variables in conflict clause
         let mut new_clause_list = Vec::new();
         let mut found_levels: BinaryHeap<usize> = BinaryHeap::new();
+        let mut distinct_levels: BTreeSet<usize> = BTreeSet::new();
 
         // Calculate levels, create clause, and update activity for learned clause
         for var in conflict_list {
-            let var_inv = var * -1;
+            let var_inv = -var;
             new_clause_list.push(var);
-            self.bump_activity(var);
             let level = *variable_level_map.get(&var_inv).unwrap();
             assert!(level != current_level || var_inv == last_decided_var);
             found_levels.push(level);
+            distinct_levels.insert(level);
         }
 
+        // Variables that explain the learned clause without having been resolved on
+        let mut reason_side: BTreeMap<usize, i32> = BTreeMap::new();
+        for var in &new_clause_list {
+            if let Some(reason) = self.analysis_reason(var.unsigned_abs() as usize, &trail, &mut reasons) {
+                for other in &reason {
+                    let other_var = other.unsigned_abs() as usize;
+                    if !conflict_side.contains_key(&other_var) {
+                        reason_side.insert(other_var, *other);
+                    }
+                }
+            }
+        }
+        let conflict_side: Vec<i32> = conflict_side.into_values().collect();
+        let reason_side: Vec<i32> = reason_side.into_values().collect();
+        self.heuristic.on_analysis(&conflict_side, &reason_side);
+        self.heuristic.on_conflict(&new_clause_list);
+
         // Learn the conflict clause
+        self.stats.learned_clauses += 1;
+        self.stats.learned_literals += new_clause_list.len();
+        self.stats.learned_lbd += distinct_levels.len();
         self.add_clause(Clause::from_vec(new_clause_list));
 
-        // Get second highest level
-        let first = found_levels.pop();
-        let backjump_level = match found_levels.pop() {
-            Some(val) => val + 1,
-            None => first.unwrap(),
-        };
+        // A conflict that does not depend on the last decision leaves a clause falsified below
+        // the current level: jump to its highest level and let it be found as the next conflict
+        if !self.clauselist[self.clauses() - 1].vars.contains(&-last_decided_var) {
+            match found_levels.peek() {
+                Some(highest) => {
+                    self.backtrack(highest + 1);
+                    return true;
+                }
+                // The empty clause was learned
+                None => return false,
+            }
+        }
 
-        // Backjump
-        self.movelist.truncate(backjump_level);
-        self.add_move(SolverMove::DecideFromConflict(
-            -1 * last_decided_var,
-            self.clauses() - 1,
-        ));
+        // The learned clause asserts the flipped decision at its second highest level
+        found_levels.pop();
+        let assertion_level = found_levels.pop().unwrap_or(0);
+
+        // Jumping further back than the threshold only undoes the conflict level, leaving the
+        // asserted literal out of order above the levels it skipped
+        if current_level - assertion_level > self.config.chrono_threshold {
+            self.stats.chrono_backtracks += 1;
+            self.backtrack(current_level);
+        } else {
+            // A trail starting with a decision has no level below it to keep
+            self.backtrack((assertion_level + 1).min(current_level));
+        }
+        self.add_move_at_level(
+            SolverMove::DecideFromConflict(-last_decided_var, self.clauses() - 1),
+            assertion_level,
+        );
 
         // Find clauses to delete
-        let check_forget_cutoff = 0.05;
-        let check_forget_rand = rand::random::<f32>();
+        let check_forget_cutoff = self.config.forget_probability;
+        let check_forget_rand = self.rng.gen::<f64>();
         let forget = check_forget_cutoff > check_forget_rand;
         if forget {
             let deletable_clauses = self.find_deletable_clauses();
-            let mut filtered_deleteable_clauses = HashSet::new();
+            let mut filtered_deleteable_clauses = BTreeSet::new();
             let cutoff: f64 =
-                0.0001 * (deletable_clauses.len() as f64) / (self.original_clause_count as f64);
+                self.config.forget_fraction * (deletable_clauses.len() as f64) / (self.original_clause_count as f64);
             for index in deletable_clauses {
-                let x = rand::random::<f64>();
+                let x = self.rng.gen::<f64>();
                 if x < cutoff {
                     filtered_deleteable_clauses.insert(index);
                 }
@@ -347,33 +938,33 @@ impl SolverState {
             self.forget_clause(&filtered_deleteable_clauses);
         }
 
-        // Decay all activity values
-        for i in 0..self.vars() * 2 {
-            self.activitylist[i] *= 0.50;
-        }
-
-        return true;
-    }
-
-    pub fn set_activity(&mut self, activity: Vec<f32>) {
-        self.activitylist = activity;
+        true
     }
 
     pub fn set_vars(&mut self, vars: usize) {
         self.vars = vars;
+        self.levels = vec![0; vars + 1];
+        self.phases = vec![None; vars + 1];
+        self.heuristic.init(vars);
+        self.xor_matrix = None;
     }
     pub fn vars(&self) -> usize {
-        return self.vars;
+        self.vars
+    }
+}
+
+impl Default for SolverState {
+    fn default() -> Self {
+        SolverState::new()
     }
 }
 
-impl ToString for SolverState {
-    fn to_string(&self) -> String {
+impl fmt::Display for SolverState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut buf = "".to_owned();
-        buf = buf + "\nAssignment:\n";
+        buf += "\nAssignment:\n";
 
-        let assignment = Assignment::from_movelist(&self.get_movelist(), self.vars());
-        buf = buf + &assignment.to_string();
-        return buf.to_string();
+        buf = buf + &self.model().to_string();
+        write!(f, "{}", buf)
     }
 }
@@ -0,0 +1,185 @@
+use std::fmt;
+use std::time::Instant;
+
+// Number of progress rows printed between repeated table headers
+const HEADER_INTERVAL: usize = 20;
+
+/// Counters collected over the course of a search
+#[derive(Debug)]
+pub struct Statistics {
+    pub decisions: usize,
+    pub propagations: usize,
+    pub conflicts: usize,
+    pub restarts: usize,
+    pub learned_clauses: usize,
+    pub deleted_clauses: usize,
+    // Backjumps replaced by undoing only the conflict level
+    pub chrono_backtracks: usize,
+    // Sum of the sizes and LBDs of all learned clauses, used for the averages
+    pub learned_literals: usize,
+    pub learned_lbd: usize,
+    pub minimized_literals: usize,
+    pub max_decision_level: usize,
+    pub eliminated_variables: usize,
+    pub failed_literals: usize,
+    pub substituted_variables: usize,
+    pub blocked_clauses: usize,
+    // Clauses removed by subsumption and literals removed by self-subsuming resolution
+    pub subsumed_clauses: usize,
+    pub strengthened_literals: usize,
+    // Clauses shortened or deleted by vivification, the literals it removed and the
+    // propagations it spent
+    pub vivified_clauses: usize,
+    pub vivified_literals: usize,
+    pub vivify_propagations: usize,
+    // Variables flipped by local search and local search runs setting the decision phases
+    pub flips: usize,
+    pub rephases: usize,
+    // Components model counting split the formula into and how many of them were cached
+    pub components: usize,
+    pub cache_hits: usize,
+    // Clauses built to explain propagations and conflicts of XOR and pseudo-Boolean constraints, and
+    // the hash cells approximate counting enumerated
+    pub xor_explanations: usize,
+    pub pb_explanations: usize,
+    pub cells: usize,
+    // Unsatisfiable cores core-guided MaxSAT relaxed
+    pub cores: usize,
+    // Clauses or groups of a MUS that model rotation showed necessary without a search
+    pub rotations: usize,
+    reports: usize,
+    start: Instant,
+}
+
+impl Statistics {
+    pub fn new() -> Statistics {
+        Statistics {
+            decisions: 0,
+            propagations: 0,
+            conflicts: 0,
+            restarts: 0,
+            learned_clauses: 0,
+            deleted_clauses: 0,
+            chrono_backtracks: 0,
+            learned_literals: 0,
+            learned_lbd: 0,
+            minimized_literals: 0,
+            max_decision_level: 0,
+            eliminated_variables: 0,
+            failed_literals: 0,
+            substituted_variables: 0,
+            blocked_clauses: 0,
+            subsumed_clauses: 0,
+            strengthened_literals: 0,
+            vivified_clauses: 0,
+            vivified_literals: 0,
+            vivify_propagations: 0,
+            flips: 0,
+            rephases: 0,
+            components: 0,
+            cache_hits: 0,
+            xor_explanations: 0,
+            pb_explanations: 0,
+            cells: 0,
+            cores: 0,
+            rotations: 0,
+            reports: 0,
+            start: Instant::now(),
+        }
+    }
+
+    pub fn moves(&self) -> usize {
+        self.decisions + self.propagations
+    }
+
+    pub fn avg_learned_len(&self) -> f64 {
+        if self.learned_clauses == 0 {
+            return 0.0;
+        }
+        self.learned_literals as f64 / self.learned_clauses as f64
+    }
+
+    pub fn avg_lbd(&self) -> f64 {
+        if self.learned_clauses == 0 {
+            return 0.0;
+        }
+        self.learned_lbd as f64 / self.learned_clauses as f64
+    }
+
+    pub fn elapsed_secs(&self) -> f64 {
+        self.start.elapsed().as_secs_f64()
+    }
+
+    pub fn propagations_per_second(&self) -> f64 {
+        let elapsed = self.elapsed_secs();
+        if elapsed == 0.0 {
+            return 0.0;
+        }
+        self.propagations as f64 / elapsed
+    }
+
+    /// Returns the next line of the progress table, preceded by the header when one is due
+    pub fn progress_report(&mut self) -> String {
+        let mut buf = "".to_owned();
+        if self.reports.is_multiple_of(HEADER_INTERVAL) {
+            buf += "c --------------------------------------------------------------------------------------------------------------\n";
+            buf += "c |  time(s) |  conflicts | restarts |  decisions | propagations | learned | deleted | avg len | avg lbd | max lv |\n";
+            buf += "c --------------------------------------------------------------------------------------------------------------\n";
+        }
+        self.reports += 1;
+        buf += &format!(
+            "c | {:>8.2} | {:>10} | {:>8} | {:>10} | {:>12} | {:>7} | {:>7} | {:>7.2} | {:>7.2} | {:>6} |",
+            self.elapsed_secs(),
+            self.conflicts,
+            self.restarts,
+            self.decisions,
+            self.propagations,
+            self.learned_clauses,
+            self.deleted_clauses,
+            self.avg_learned_len(),
+            self.avg_lbd(),
+            self.max_decision_level,
+        );
+        buf
+    }
+}
+
+impl Default for Statistics {
+    fn default() -> Self {
+        Statistics::new()
+    }
+}
+
+impl fmt::Display for Statistics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "c decisions          : {}", self.decisions)?;
+        writeln!(f, "c propagations       : {}", self.propagations)?;
+        writeln!(f, "c conflicts          : {}", self.conflicts)?;
+        writeln!(f, "c restarts           : {}", self.restarts)?;
+        writeln!(f, "c learned clauses    : {}", self.learned_clauses)?;
+        writeln!(f, "c deleted clauses    : {}", self.deleted_clauses)?;
+        writeln!(f, "c chrono backtracks  : {}", self.chrono_backtracks)?;
+        writeln!(f, "c avg learned length : {:.2}", self.avg_learned_len())?;
+        writeln!(f, "c avg learned lbd    : {:.2}", self.avg_lbd())?;
+        writeln!(f, "c minimized literals : {}", self.minimized_literals)?;
+        writeln!(f, "c max decision level : {}", self.max_decision_level)?;
+        writeln!(f, "c eliminated vars    : {}", self.eliminated_variables)?;
+        writeln!(f, "c failed literals    : {}", self.failed_literals)?;
+        writeln!(f, "c substituted vars   : {}", self.substituted_variables)?;
+        writeln!(f, "c blocked clauses    : {}", self.blocked_clauses)?;
+        writeln!(f, "c subsumed clauses   : {}", self.subsumed_clauses)?;
+        writeln!(f, "c strengthened lits  : {}", self.strengthened_literals)?;
+        writeln!(f, "c vivified clauses   : {}", self.vivified_clauses)?;
+        writeln!(f, "c vivified literals  : {}", self.vivified_literals)?;
+        writeln!(f, "c flips              : {}", self.flips)?;
+        writeln!(f, "c rephases           : {}", self.rephases)?;
+        writeln!(f, "c components         : {}", self.components)?;
+        writeln!(f, "c cache hits         : {}", self.cache_hits)?;
+        writeln!(f, "c xor explanations   : {}", self.xor_explanations)?;
+        writeln!(f, "c pb explanations    : {}", self.pb_explanations)?;
+        writeln!(f, "c hash cells         : {}", self.cells)?;
+        writeln!(f, "c cores              : {}", self.cores)?;
+        writeln!(f, "c rotations          : {}", self.rotations)?;
+        write!(f, "c propagations/sec   : {:.0}", self.propagations_per_second())
+    }
+}
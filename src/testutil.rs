@@ -0,0 +1,65 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::solver_state::SolverState;
+
+/// Every total assignment of the first `vars` variables, as literals
+pub fn assignments(vars: usize) -> impl Iterator<Item = Vec<i32>> {
+    (0..1u32 << vars).map(move |bits| (1..=vars as i32).map(|var| if bits >> (var - 1) & 1 == 1 { var } else { -var }).collect())
+}
+
+/// Whether a total assignment satisfies the original clauses and the XOR and pseudo-Boolean
+/// constraints of the state
+pub fn is_model(state: &SolverState, model: &[i32]) -> bool {
+    let value = |var: usize| model[var - 1] > 0;
+    let clauses_hold = state.clauselist()[..state.original_clause_count]
+        .iter()
+        .all(|clause| clause.vars.iter().any(|literal| model.contains(literal)));
+    let xors_hold = state.xors().iter().all(|xor| xor.vars.iter().filter(|var| value(**var)).count() % 2 == xor.parity as usize);
+    let pbs_hold = state.pbs().iter().all(|pb| {
+        let sum: u64 = pb.terms.iter().filter(|(_, literal)| model.contains(literal)).map(|(coefficient, _)| coefficient).sum();
+        sum >= pb.bound
+    });
+    clauses_hold && xors_hold && pbs_hold
+}
+
+/// Models of the state over all of its variables, by brute force
+pub fn brute_force_models(state: &SolverState) -> Vec<Vec<i32>> {
+    assignments(state.vars()).filter(|model| is_model(state, model)).collect()
+}
+
+/// Whether some assignment of the first `vars` variables satisfies every clause
+pub fn satisfiable(clauses: &[&Vec<i32>], vars: usize) -> bool {
+    assignments(vars).any(|model| clauses.iter().all(|clause| clause.iter().any(|literal| model.contains(literal))))
+}
+
+/// `count` clauses of one to `max_len` random literals over the first `vars` variables
+pub fn random_clauses(rng: &mut impl Rng, vars: i32, count: usize, max_len: i32) -> Vec<Vec<i32>> {
+    (0..count)
+        .map(|_| (0..rng.gen_range(1..=max_len)).map(|_| rng.gen_range(1..=vars) * if rng.gen() { 1 } else { -1 }).collect())
+        .collect()
+}
+
+/// A random 3-SAT formula in DIMACS CNF, the same for the same seed
+pub fn random_3sat(vars: i32, clauses: usize, seed: u64) -> String {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut buf = format!("p cnf {} {}\n", vars, clauses);
+    for _ in 0..clauses {
+        for _ in 0..3 {
+            let var = rng.gen_range(1..=vars);
+            let lit = if rng.gen::<bool>() { var } else { -var };
+            buf += &format!("{} ", lit);
+        }
+        buf += "0\n";
+    }
+    buf
+}
+
+/// The clauses as a DIMACS CNF formula
+pub fn dimacs(vars: i32, clauses: &[Vec<i32>]) -> String {
+    let mut input = format!("p cnf {vars} {}\n", clauses.len());
+    for clause in clauses {
+        input += &format!("{} 0\n", clause.iter().map(|literal| literal.to_string()).collect::<Vec<_>>().join(" "));
+    }
+    input
+}
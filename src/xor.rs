@@ -0,0 +1,328 @@
+use crate::assignment::Assignment;
+
+/// Constraint that an odd number of its variables are true if `parity` is set, and an even
+/// number otherwise
+#[derive(Clone, Debug, PartialEq)]
+pub struct Xor {
+    pub vars: Vec<usize>,
+    pub parity: bool,
+}
+
+impl Xor {
+    /// Builds the constraint in sorted order, dropping variables that occur twice as they
+    /// cancel out
+    pub fn new(mut vars: Vec<usize>, parity: bool) -> Xor {
+        vars.sort();
+        let mut kept: Vec<usize> = Vec::new();
+        for var in vars {
+            if kept.last() == Some(&var) {
+                kept.pop();
+            } else {
+                kept.push(var);
+            }
+        }
+        Xor { vars: kept, parity }
+    }
+
+    /// Clause explaining why the constraint implies `implied`, or why it is violated when that
+    /// is None, where `is_false` tells the literals that were false at that point: the implied
+    /// literal followed by the false literal of every other variable.
+    pub fn explain(&self, implied: Option<i32>, is_false: impl Fn(i32) -> bool) -> Vec<i32> {
+        let mut clause: Vec<i32> = implied.into_iter().collect();
+        for var in &self.vars {
+            let literal = *var as i32;
+            if implied.map(i32::abs) != Some(literal) {
+                clause.push(if is_false(literal) { literal } else { -literal });
+            }
+        }
+        clause
+    }
+
+    /// First variable of the constraint without a value
+    pub fn unassigned(&self, assignment: &Assignment) -> Option<usize> {
+        self.vars.iter().copied().find(|var| assignment[*var].is_none())
+    }
+
+    /// The constraint as literals whose XOR is true, as on the `x` lines of CryptoMiniSat's
+    /// DIMACS extension, with the first variable negated for an even parity. Empty for an
+    /// empty constraint, which can only be written down when it is unsatisfiable.
+    pub fn literals(&self) -> Vec<i32> {
+        let mut literals: Vec<i32> = self.vars.iter().map(|var| *var as i32).collect();
+        if !self.parity {
+            if let Some(first) = literals.first_mut() {
+                *first = -*first;
+            }
+        }
+        literals
+    }
+
+    /// Clauses equivalent to the constraint, chaining fresh variables numbered from `next_var`
+    /// up as the partial sums so the clauses grow linearly with its length. The fresh
+    /// variables are determined by the others, so the number of models does not change.
+    pub fn to_clauses(&self, next_var: &mut usize) -> Vec<Vec<i32>> {
+        let Some((first, rest)) = self.vars.split_first() else {
+            return if self.parity { vec![Vec::new()] } else { Vec::new() };
+        };
+        let mut clauses = Vec::new();
+        let mut sum = *first as i32;
+        for var in rest {
+            let (var, fresh) = (*var as i32, *next_var as i32);
+            *next_var += 1;
+            // fresh <-> sum xor var
+            clauses.push(vec![-fresh, sum, var]);
+            clauses.push(vec![-fresh, -sum, -var]);
+            clauses.push(vec![fresh, -sum, var]);
+            clauses.push(vec![fresh, sum, -var]);
+            sum = fresh;
+        }
+        clauses.push(vec![if self.parity { sum } else { -sum }]);
+        clauses
+    }
+}
+
+// Bit set over variables, one bit per variable index
+fn bits(vars: &[usize], words: usize) -> Vec<u64> {
+    let mut bits = vec![0; words];
+    for var in vars {
+        bits[var / 64] |= 1 << (var % 64);
+    }
+    bits
+}
+
+fn has_bit(bits: &[u64], var: usize) -> bool {
+    bits[var / 64] & (1 << (var % 64)) != 0
+}
+
+fn set_bit(bits: &mut [u64], var: usize, value: bool) {
+    if value {
+        bits[var / 64] |= 1 << (var % 64);
+    } else {
+        bits[var / 64] &= !(1 << (var % 64));
+    }
+}
+
+// Sum of XOR constraints, with the variable eliminated from every other row if it has one
+struct Row {
+    bits: Vec<u64>,
+    parity: bool,
+    pivot: Option<usize>,
+}
+
+/// XOR constraints as rows of bits over the variables, kept in reduced row echelon form over
+/// the unassigned variables as the trail changes: every row has an unassigned pivot variable no
+/// other row contains, unless all of its variables are assigned. A row whose pivot is assigned
+/// moves it to another of its unassigned variables, eliminated from the other rows in turn.
+/// Rows stay sums of the constraints whatever is assigned, so unassigning a variable only gives
+/// it as the pivot to a row left without one. A row with one unassigned variable implies it,
+/// which no single constraint may do, and an assigned row of the wrong parity is a conflict.
+///
+/// Without elimination the rows are the constraints themselves, propagated one at a time.
+pub struct XorMatrix {
+    rows: Vec<Row>,
+    eliminate: bool,
+    // The assigned variables, and those of them that are true
+    assigned: Vec<u64>,
+    values: Vec<u64>,
+}
+
+impl XorMatrix {
+    /// Rows of `xors` over `vars` variables with every variable unassigned, eliminated if
+    /// `eliminate` is set
+    pub fn new(xors: &[Xor], vars: usize, eliminate: bool) -> XorMatrix {
+        let vars = xors.iter().flat_map(|xor| xor.vars.iter().copied()).fold(vars, usize::max);
+        let words = vars / 64 + 1;
+        let rows = xors
+            .iter()
+            .map(|xor| Row {
+                bits: bits(&xor.vars, words),
+                parity: xor.parity,
+                pivot: None,
+            })
+            .collect();
+        let mut matrix = XorMatrix {
+            rows,
+            eliminate,
+            assigned: vec![0; words],
+            values: vec![0; words],
+        };
+        if eliminate {
+            for row in 0..matrix.rows.len() {
+                if let Some(var) = matrix.open_var(row) {
+                    matrix.set_pivot(row, var);
+                }
+            }
+        }
+        matrix
+    }
+
+    // First unassigned variable of a row
+    fn open_var(&self, row: usize) -> Option<usize> {
+        let bits = &self.rows[row].bits;
+        (0..bits.len()).find_map(|word| {
+            let open = bits[word] & !self.assigned[word];
+            (open != 0).then(|| word * 64 + open.trailing_zeros() as usize)
+        })
+    }
+
+    // Makes `var` the pivot of `row`, adding the row to every other row containing it
+    fn set_pivot(&mut self, row: usize, var: usize) {
+        self.rows[row].pivot = Some(var);
+        let (pivot_bits, pivot_parity) = (self.rows[row].bits.clone(), self.rows[row].parity);
+        for (index, other) in self.rows.iter_mut().enumerate() {
+            if index != row && has_bit(&other.bits, var) {
+                for (word, pivot_word) in other.bits.iter_mut().zip(&pivot_bits) {
+                    *word ^= pivot_word;
+                }
+                other.parity ^= pivot_parity;
+            }
+        }
+    }
+
+    pub fn assign(&mut self, literal: i32) {
+        let var = literal.unsigned_abs() as usize;
+        set_bit(&mut self.assigned, var, true);
+        set_bit(&mut self.values, var, literal > 0);
+        if !self.eliminate {
+            return;
+        }
+        if let Some(row) = (0..self.rows.len()).find(|row| self.rows[*row].pivot == Some(var)) {
+            match self.open_var(row) {
+                Some(other) => self.set_pivot(row, other),
+                None => self.rows[row].pivot = None,
+            }
+        }
+    }
+
+    pub fn unassign(&mut self, literal: i32) {
+        let var = literal.unsigned_abs() as usize;
+        set_bit(&mut self.assigned, var, false);
+        if !self.eliminate {
+            return;
+        }
+        if let Some(row) =
+            (0..self.rows.len()).find(|row| self.rows[*row].pivot.is_none() && has_bit(&self.rows[*row].bits, var))
+        {
+            self.set_pivot(row, var);
+        }
+    }
+
+    /// First row that is violated, with None, or that implies the value of its only unassigned
+    /// variable, with the literal it implies. The row comes as the XOR constraint it sums to.
+    pub fn propagate(&self) -> Option<(Option<i32>, Xor)> {
+        for row in &self.rows {
+            let mut parity = row.parity;
+            let mut open = None;
+            let mut unassigned = 0;
+            for word in 0..row.bits.len() {
+                let open_bits = row.bits[word] & !self.assigned[word];
+                if open_bits != 0 && open.is_none() {
+                    open = Some(word * 64 + open_bits.trailing_zeros() as usize);
+                }
+                unassigned += open_bits.count_ones();
+                parity ^= (row.bits[word] & self.assigned[word] & self.values[word]).count_ones() % 2 == 1;
+            }
+            let implied = match (unassigned, open) {
+                (0, _) if parity => None,
+                (1, Some(var)) => Some(if parity { var as i32 } else { -(var as i32) }),
+                _ => continue,
+            };
+            let vars = (0..row.bits.len() * 64).filter(|var| has_bit(&row.bits, *var)).collect();
+            return Some((implied, Xor { vars, parity: row.parity }));
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num_bigint::BigUint;
+
+    use super::{Xor, XorMatrix};
+    use crate::assignment::Assignment;
+    use crate::count::count;
+    use crate::enumerate::models;
+    use crate::parser::parse_reader;
+    use crate::solver::solve;
+    use crate::testutil::brute_force_models;
+
+    #[test]
+    fn explanations_imply_and_falsify() {
+        let xor = Xor::new(vec![3, 1, 2, 4, 4], true);
+        assert_eq!(xor.vars, vec![1, 2, 3]);
+
+        // 1 xor 2 xor 3 with 1 true and 2 false forces 3 false
+        let mut matrix = XorMatrix::new(std::slice::from_ref(&xor), 3, false);
+        matrix.assign(1);
+        matrix.assign(-2);
+        assert_eq!(matrix.propagate(), Some((Some(-3), xor.clone())));
+        assert_eq!(xor.explain(Some(-3), |literal| literal == 2), vec![-3, -1, 2]);
+        assert_eq!(xor.unassigned(&Assignment::from_literals(&[1, -2], 3)), Some(3));
+
+        matrix.assign(3);
+        assert_eq!(matrix.propagate(), Some((None, xor.clone())));
+        assert_eq!(xor.explain(None, |literal| literal == 2), vec![-1, 2, -3]);
+        matrix.unassign(3);
+        matrix.assign(-3);
+        assert_eq!(matrix.propagate(), None);
+        matrix.unassign(-3);
+        matrix.unassign(-2);
+        assert_eq!(matrix.propagate(), None);
+    }
+
+    #[test]
+    fn elimination_combines_constraints() {
+        // Adding the two constraints leaves 3 alone, which neither does by itself
+        let xors = [Xor::new(vec![1, 2, 3], true), Xor::new(vec![1, 2], false)];
+        assert_eq!(XorMatrix::new(&xors, 3, false).propagate(), None);
+        let mut matrix = XorMatrix::new(&xors, 3, true);
+        assert_eq!(matrix.propagate(), Some((Some(3), Xor::new(vec![3], true))));
+
+        // The same sum conflicts once 3 is false
+        matrix.assign(-3);
+        assert_eq!(matrix.propagate(), Some((None, Xor::new(vec![3], true))));
+
+        // Unassigning 3 makes it a pivot again, and assigning the pivot 1 moves it to 2, which
+        // the first row then implies
+        matrix.unassign(-3);
+        matrix.assign(1);
+        assert_eq!(matrix.propagate(), Some((Some(2), Xor::new(vec![1, 2], false))));
+
+        // Contradicting constraints sum to an empty row of odd parity
+        let xors = [Xor::new(vec![1, 2], true), Xor::new(vec![1, 2], false)];
+        assert_eq!(XorMatrix::new(&xors, 2, true).propagate(), Some((None, Xor::new(vec![], true))));
+    }
+
+    #[test]
+    fn search_respects_xors() {
+        // 2 xor 3 xor 6 is even, written with a negated literal
+        let input = "p cnf 6 5\n1 2 3 0\nx1 2 4 5 0\n-2 4 0\nx -2 3 6 0\n-5 -6 0\n";
+        let state = parse_reader(input.as_bytes()).unwrap();
+        let xors = state.xors().to_vec();
+        assert_eq!(xors, vec![Xor::new(vec![1, 2, 4, 5], true), Xor::new(vec![2, 3, 6], false)]);
+        assert_eq!(state.original_clause_count, 3);
+
+        let mut expected = brute_force_models(&state);
+        expected.sort();
+
+        for gauss in [false, true] {
+            let mut state = parse_reader(input.as_bytes()).unwrap();
+            let mut config = state.config().clone();
+            config.gauss = gauss;
+            state.set_config(config.clone());
+            let mut found: Vec<Vec<i32>> = models(&mut state).collect();
+            found.sort();
+            assert_eq!(found, expected);
+            assert!(state.stats().xor_explanations > 0);
+
+            // Explanations only serve conflict analysis and are never kept as clauses
+            let mut state = parse_reader(input.as_bytes()).unwrap();
+            state.set_config(config);
+            assert!(solve(&mut state));
+            let stats = state.stats();
+            assert_eq!(state.clauses(), state.original_clause_count + stats.learned_clauses - stats.deleted_clauses);
+        }
+
+        let mut state = parse_reader(input.as_bytes()).unwrap();
+        assert_eq!(count(&mut state), BigUint::from(expected.len()));
+    }
+}